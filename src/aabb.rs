@@ -0,0 +1,129 @@
+use crate::vec3::Vec3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub const EMPTY: Aabb = Aabb {
+        min: Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+        max: Vec3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+    };
+
+    pub fn new(a: Vec3, b: Vec3) -> Aabb {
+        Aabb {
+            min: Vec3::min(a, b),
+            max: Vec3::max(a, b),
+        }
+    }
+
+    /// Widens every axis thinner than `delta`, so flat primitives still get
+    /// a box with volume.
+    #[must_use]
+    pub fn pad(self, delta: f32) -> Aabb {
+        let mut min = self.min;
+        let mut max = self.max;
+        let extent = self.extent();
+        let half = 0.5 * delta;
+        let grow = Vec3::new(
+            if extent.x() < delta { half } else { 0.0 },
+            if extent.y() < delta { half } else { 0.0 },
+            if extent.z() < delta { half } else { 0.0 },
+        );
+        min -= grow;
+        max += grow;
+        Aabb { min, max }
+    }
+
+    pub fn is_finite(&self) -> bool {
+        (0..3).all(|axis| self.min[axis].is_finite() && self.max[axis].is_finite())
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn union(self, other: Aabb) -> Aabb {
+        Aabb {
+            min: Vec3::min(self.min, other.min),
+            max: Vec3::max(self.max, other.max),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn grow(self, p: Vec3) -> Aabb {
+        Aabb {
+            min: Vec3::min(self.min, p),
+            max: Vec3::max(self.max, p),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn centroid(self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn extent(self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn corners(self) -> [Vec3; 8] {
+        [0, 1, 2, 3, 4, 5, 6, 7].map(|corner| {
+            Vec3::new(
+                if corner & 1 == 0 {
+                    self.min.x()
+                } else {
+                    self.max.x()
+                },
+                if corner & 2 == 0 {
+                    self.min.y()
+                } else {
+                    self.max.y()
+                },
+                if corner & 4 == 0 {
+                    self.min.z()
+                } else {
+                    self.max.z()
+                },
+            )
+        })
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn surface_area(self) -> f32 {
+        let d = self.extent();
+        if d.x() < 0.0 || d.y() < 0.0 || d.z() < 0.0 {
+            return 0.0;
+        }
+        2.0 * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+    }
+
+    /// Slab test against a ray given as origin and reciprocal direction.
+    /// Returns the entry distance when the ray overlaps `[t_min, t_max]`.
+    #[inline]
+    pub fn hit(&self, origin: Vec3, inv_dir: Vec3, t_min: f32, t_max: f32) -> Option<f32> {
+        let mut t0 = t_min;
+        let mut t1 = t_max;
+        for axis in 0..3 {
+            let near = (self.min[axis] - origin[axis]) * inv_dir[axis];
+            let far = (self.max[axis] - origin[axis]) * inv_dir[axis];
+            let (near, far) = if near <= far {
+                (near, far)
+            } else {
+                (far, near)
+            };
+            // max/min drop the NaN produced by 0 * inf when the ray lies on a slab plane
+            t0 = t0.max(near);
+            t1 = t1.min(far);
+            if t1 < t0 {
+                return None;
+            }
+        }
+        Some(t0)
+    }
+}
@@ -0,0 +1,69 @@
+use crate::aov::Aovs;
+use crate::render::Sample;
+use crate::vec3::Vec3;
+
+/// When adaptive sampling considers a pixel converged.
+#[derive(Debug, Clone, Copy)]
+pub struct Adaptive {
+    /// Largest acceptable standard error of the mean, relative to the
+    /// pixel's luminance.
+    pub threshold: f32,
+    /// Samples every pixel gets before its error is trusted.
+    pub min_samples: u32,
+}
+
+/// Running mean of a pixel's samples, with the variance of their luminance
+/// tracked by Welford's algorithm.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PixelEstimate {
+    mean: Vec3,
+    /// Sum of squared luminance deviations from the mean.
+    m2: f32,
+    samples: u32,
+    aovs: Aovs,
+}
+
+impl PixelEstimate {
+    pub fn add(&mut self, sample: Sample) {
+        let old_luminance = self.mean.luminance();
+        self.samples += 1;
+        let n = self.samples as f32;
+        self.mean += (sample.color - self.mean) / n;
+        let luminance = sample.color.luminance();
+        self.m2 += (luminance - old_luminance) * (luminance - self.mean.luminance());
+        self.aovs.add(&sample.aovs, n);
+    }
+
+    pub fn mean(&self) -> Vec3 {
+        self.mean
+    }
+
+    /// Variance of the mean's luminance, infinite before there are two
+    /// samples to estimate it from.
+    pub fn variance(&self) -> f32 {
+        if self.samples < 2 {
+            return f32::INFINITY;
+        }
+        let n = self.samples as f32;
+        self.m2 / (n - 1.0) / n
+    }
+
+    /// Averages of the render passes over the samples.
+    pub fn aovs(&self) -> &Aovs {
+        &self.aovs
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    pub fn converged(&self, adaptive: &Adaptive) -> bool {
+        if self.samples < adaptive.min_samples.max(2) {
+            return false;
+        }
+        let standard_error = self.variance().sqrt();
+        // The floor keeps nearly black pixels from needing an absurdly small
+        // absolute error
+        standard_error <= adaptive.threshold * self.mean.luminance().max(0.01)
+    }
+}
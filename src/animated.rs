@@ -0,0 +1,151 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, HittableEnum};
+use crate::ray::Ray;
+use crate::sampler::Samplers;
+use crate::vec3::Vec3;
+
+use std::ops::{Add, Mul};
+
+/// Values at increasing times, interpolated linearly in between and held
+/// constant before the first and after the last key.
+pub struct Keyframes<T> {
+    keys: Vec<(f32, T)>,
+}
+
+impl<T: Copy + Add<Output = T> + Mul<f32, Output = T>> Keyframes<T> {
+    pub fn constant(value: T) -> Keyframes<T> {
+        Keyframes {
+            keys: vec![(0.0, value)],
+        }
+    }
+
+    /// Keys may be given in any order but there must be at least one.
+    pub fn new(mut keys: Vec<(f32, T)>) -> Keyframes<T> {
+        assert!(!keys.is_empty(), "keyframes need at least one key");
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Keyframes { keys }
+    }
+
+    pub fn at(&self, time: f32) -> T {
+        let next = self.keys.partition_point(|(t, _)| *t <= time);
+        if next == 0 {
+            return self.keys[0].1;
+        }
+        if next == self.keys.len() {
+            return self.keys[next - 1].1;
+        }
+        let (t0, v0) = self.keys[next - 1];
+        let (t1, v1) = self.keys[next];
+        let s = (time - t0) / (t1 - t0);
+        v0 * (1.0 - s) + v1 * s
+    }
+
+    fn is_constant(&self) -> bool {
+        self.keys.len() == 1
+    }
+
+    fn values(&self) -> impl Iterator<Item = T> + '_ {
+        self.keys.iter().map(|(_, v)| *v)
+    }
+}
+
+/// Object moving over time: rotated around its own center, then offset by
+/// a translation. Rays are intersected with the object where it is at the
+/// ray's time.
+pub struct Animated {
+    object: Box<HittableEnum>,
+    translation: Keyframes<Vec3>,
+    axis: Vec3,
+    /// Rotation around `axis` in degrees.
+    rotation: Keyframes<f32>,
+    pivot: Vec3,
+    bounds: Aabb,
+}
+
+impl Animated {
+    pub fn new(object: HittableEnum) -> Animated {
+        let bounds = object.bounding_box();
+        Animated {
+            object: Box::new(object),
+            translation: Keyframes::constant(Vec3::ZERO),
+            axis: Vec3::new(0.0, 1.0, 0.0),
+            rotation: Keyframes::constant(0.0),
+            pivot: bounds.centroid(),
+            bounds,
+        }
+    }
+
+    pub fn with_translation(mut self, translation: Keyframes<Vec3>) -> Animated {
+        self.translation = translation;
+        self.bounds = self.motion_bounds();
+        self
+    }
+
+    pub fn with_rotation(mut self, axis: Vec3, degrees: Keyframes<f32>) -> Animated {
+        self.axis = axis.unit();
+        self.rotation = degrees;
+        self.bounds = self.motion_bounds();
+        self
+    }
+
+    /// Box covering the object at every time. Translations are piecewise
+    /// linear, so the keyframes bound them; a changing rotation is bounded by
+    /// the sphere the object sweeps around its pivot.
+    fn motion_bounds(&self) -> Aabb {
+        let object_bounds = self.object.bounding_box();
+        let local = if self.rotation.is_constant() {
+            let angle = self.rotation.at(0.0);
+            object_bounds
+                .corners()
+                .into_iter()
+                .fold(Aabb::EMPTY, |acc, p| {
+                    acc.grow(self.pivot + rotate(p - self.pivot, self.axis, angle))
+                })
+        } else {
+            let radius = (0..3)
+                .map(|axis| {
+                    let near = (object_bounds.min[axis] - self.pivot[axis]).abs();
+                    let far = (object_bounds.max[axis] - self.pivot[axis]).abs();
+                    near.max(far).powi(2)
+                })
+                .sum::<f32>()
+                .sqrt();
+            let r = Vec3::new(radius, radius, radius);
+            Aabb::new(self.pivot - r, self.pivot + r)
+        };
+        self.translation.values().fold(Aabb::EMPTY, |acc, offset| {
+            acc.union(Aabb::new(local.min + offset, local.max + offset))
+        })
+    }
+}
+
+impl Hittable for Animated {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32, sampler: &mut Samplers) -> Option<HitRecord<'_>> {
+        let offset = self.translation.at(r.time);
+        let angle = self.rotation.at(r.time);
+
+        // Move the ray into the object's frame at rest
+        let origin = self.pivot + rotate(r.origin - offset - self.pivot, self.axis, -angle);
+        let direction = rotate(r.direction, self.axis, -angle);
+        let mut rec =
+            self.object
+                .hit(Ray::new(origin, direction, r.time), t_min, t_max, sampler)?;
+
+        rec.p = self.pivot + rotate(rec.p - self.pivot, self.axis, angle) + offset;
+        rec.normal = rotate(rec.normal, self.axis, angle);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+/// Rotates `v` by `degrees` around the unit vector `axis` (Rodrigues).
+fn rotate(v: Vec3, axis: Vec3, degrees: f32) -> Vec3 {
+    if degrees == 0.0 {
+        return v;
+    }
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    v * cos + Vec3::cross(axis, v) * sin + axis * (Vec3::dot(axis, v) * (1.0 - cos))
+}
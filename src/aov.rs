@@ -0,0 +1,139 @@
+use crate::tonemap::{DisplayTransform, srgb_encode};
+use crate::vec3::Vec3;
+
+/// Render pass written next to the image for compositing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Aov {
+    /// Surface color, seen through mirrors and glass
+    Albedo,
+    /// Shading normal, seen through mirrors and glass
+    Normal,
+    /// Distance of the nearest first hit along the camera's viewing
+    /// direction, infinite where every ray escapes to the background
+    Depth,
+    /// World position of the nearest first hit, infinite where every ray
+    /// escapes to the background
+    Position,
+    /// Number of the object at the first hit, 0 for none
+    ObjectId,
+    /// Light reflected once on its way to the camera
+    Direct,
+    /// Light reflected more than once
+    Indirect,
+    /// Emitters and background seen directly
+    Emission,
+}
+
+impl Aov {
+    pub fn name(self) -> &'static str {
+        match self {
+            Aov::Albedo => "albedo",
+            Aov::Normal => "normal",
+            Aov::Depth => "depth",
+            Aov::Position => "position",
+            Aov::ObjectId => "object_id",
+            Aov::Direct => "direct",
+            Aov::Indirect => "indirect",
+            Aov::Emission => "emission",
+        }
+    }
+
+    /// Channel names of the pass in a multi-channel EXR.
+    pub fn channels(self) -> &'static [&'static str] {
+        match self {
+            Aov::Depth => &["Z"],
+            Aov::ObjectId => &["id"],
+            Aov::Normal | Aov::Position => &["X", "Y", "Z"],
+            _ => &["R", "G", "B"],
+        }
+    }
+
+    /// Value of the pass in a pixel; single channel passes repeat it in all
+    /// three components.
+    pub fn value(self, aovs: &Aovs) -> Vec3 {
+        let scalar = |v: f32| Vec3::new(v, v, v);
+        match self {
+            Aov::Albedo => aovs.albedo,
+            Aov::Normal => aovs.normal,
+            Aov::Depth => scalar(aovs.depth),
+            Aov::Position => aovs.position,
+            Aov::ObjectId => scalar(aovs.object_id as f32),
+            Aov::Direct => aovs.direct,
+            Aov::Indirect => aovs.indirect,
+            Aov::Emission => aovs.emission,
+        }
+    }
+
+    /// How `values` of this pass are shown in 8-bit images, which can't hold
+    /// them as they are.
+    pub fn visualize<'a>(
+        self,
+        values: &[Vec3],
+        display: &'a DisplayTransform,
+    ) -> Box<dyn Fn(Vec3) -> Vec3 + 'a> {
+        let max = values
+            .iter()
+            .map(|v| v.x())
+            .filter(|x| x.is_finite())
+            .fold(0.0_f32, f32::max);
+        match self {
+            Aov::Albedo => Box::new(|v| v.map(srgb_encode)),
+            Aov::Normal => Box::new(|v| 0.5 * (v + Vec3::new(1.0, 1.0, 1.0))),
+            // Ramps from black at the camera or for object 0 to white at
+            // the largest finite value in the image, and for misses
+            Aov::Depth | Aov::ObjectId if max > 0.0 => Box::new(move |v| v / max),
+            Aov::Depth | Aov::ObjectId | Aov::Position => Box::new(|v| v),
+            Aov::Direct | Aov::Indirect | Aov::Emission => Box::new(|v| display.apply(v)),
+        }
+    }
+}
+
+/// Passes of one path, or their average over a pixel's samples.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Aovs {
+    pub albedo: Vec3,
+    pub normal: Vec3,
+    /// Taken with `position` from the nearest sample, like a z-buffer,
+    /// since an average across an edge makes up a surface between the two
+    /// and an average with a miss is infinite.
+    pub depth: f32,
+    pub position: Vec3,
+    /// Taken from the first sample, since an average of ids means nothing.
+    pub object_id: u32,
+    pub direct: Vec3,
+    pub indirect: Vec3,
+    pub emission: Vec3,
+}
+
+impl Aovs {
+    /// Folds `other` into a running mean that becomes one over `n` samples.
+    pub fn add(&mut self, other: &Aovs, n: f32) {
+        self.albedo += (other.albedo - self.albedo) / n;
+        self.normal += (other.normal - self.normal) / n;
+        if n <= 1.0 || other.depth < self.depth {
+            self.depth = other.depth;
+            self.position = other.position;
+        }
+        if n <= 1.0 {
+            self.object_id = other.object_id;
+        }
+        self.direct += (other.direct - self.direct) / n;
+        self.indirect += (other.indirect - self.indirect) / n;
+        self.emission += (other.emission - self.emission) / n;
+    }
+
+    /// Adds light that reached the camera after `bounces` non-specular
+    /// bounces to the matching pass.
+    pub fn add_light(&mut self, bounces: u32, light: Vec3) {
+        match bounces {
+            0 => self.emission += light,
+            1 => self.direct += light,
+            _ => self.indirect += light,
+        }
+    }
+
+    /// Sum of the lighting passes, the radiance of the path.
+    pub fn radiance(&self) -> Vec3 {
+        self.emission + self.direct + self.indirect
+    }
+}
@@ -0,0 +1,48 @@
+use crate::environment::EnvironmentMap;
+use crate::sampler::Samplers;
+use crate::vec3::Vec3;
+
+use std::sync::Arc;
+
+/// Radiance arriving along rays that leave the scene.
+#[derive(Clone)]
+pub enum Background {
+    /// White to blue gradient along the vertical axis.
+    Sky,
+    Solid(Vec3),
+    Environment(Arc<EnvironmentMap>),
+}
+
+impl Background {
+    pub fn color(&self, direction: Vec3) -> Vec3 {
+        match self {
+            Background::Sky => {
+                let unit_direction = direction.unit();
+                let t = 0.5 * (unit_direction.y() + 1.0);
+                (1.0 - t) * Vec3::new(1.0, 1.0, 1.0) + t * Vec3::new(0.5, 0.7, 1.0)
+            }
+            Background::Solid(color) => *color,
+            Background::Environment(map) => map.color(direction),
+        }
+    }
+
+    /// Whether the background is sampled as a light.
+    pub fn is_sampled(&self) -> bool {
+        matches!(self, Background::Environment(_))
+    }
+
+    /// Solid angle density of `sample` picking `direction`.
+    pub fn pdf_value(&self, direction: Vec3) -> f32 {
+        match self {
+            Background::Environment(map) => map.pdf_value(direction),
+            _ => 0.0,
+        }
+    }
+
+    pub fn sample(&self, sampler: &mut Samplers) -> Vec3 {
+        match self {
+            Background::Environment(map) => map.sample(sampler),
+            _ => Vec3::random_unit_vector(sampler),
+        }
+    }
+}
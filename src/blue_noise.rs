@@ -0,0 +1,153 @@
+use crate::sampler::{Pcg, Sampler, hash_combine};
+
+use std::sync::OnceLock;
+
+const SIZE: usize = 64;
+
+/// Blue noise sampler: within a dimension, neighboring pixels get values
+/// from a tiled void-and-cluster mask, so the error of a pixel tends to
+/// cancel with its neighbors'. Successive samples of a pixel advance along
+/// the R1 and R2 rank-1 lattices, which keeps them well spread too.
+pub struct BlueNoise {
+    seed: u32,
+    pixel: (u32, u32),
+    sample: u32,
+    dimension: u32,
+}
+
+impl BlueNoise {
+    pub fn new(seed: u32, pixel: (u32, u32), sample: u32) -> BlueNoise {
+        BlueNoise {
+            seed,
+            pixel,
+            sample,
+            dimension: 0,
+        }
+    }
+
+    /// Mask value at the pixel, with the tiling shifted differently in every
+    /// dimension so the dimensions don't repeat each other.
+    fn next_mask_value(&mut self) -> f64 {
+        let offset = hash_combine(self.seed, self.dimension);
+        self.dimension += 1;
+        let x = (self.pixel.0 as usize + (offset & 0xffff) as usize) % SIZE;
+        let y = (self.pixel.1 as usize + (offset >> 16) as usize) % SIZE;
+        mask()[y * SIZE + x] as f64
+    }
+}
+
+impl Sampler for BlueNoise {
+    fn next_1d(&mut self) -> f32 {
+        // Golden ratio conjugate
+        const ALPHA: f64 = 0.618_033_988_749_894_9;
+        let value = self.next_mask_value();
+        to_f32((value + self.sample as f64 * ALPHA).fract())
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        // Inverses of the plastic number and its square
+        const ALPHA: [f64; 2] = [0.754_877_666_246_692_7, 0.569_840_290_998_053_3];
+        let x = self.next_mask_value();
+        let y = self.next_mask_value();
+        let sample = self.sample as f64;
+        (
+            to_f32((x + sample * ALPHA[0]).fract()),
+            to_f32((y + sample * ALPHA[1]).fract()),
+        )
+    }
+}
+
+fn to_f32(value: f64) -> f32 {
+    (value as f32).min(1.0 - f32::EPSILON / 2.0)
+}
+
+/// Tileable blue noise mask, generated on first use.
+fn mask() -> &'static [f32] {
+    static MASK: OnceLock<Vec<f32>> = OnceLock::new();
+    MASK.get_or_init(void_and_cluster)
+}
+
+/// Ulichney's void-and-cluster method: every pixel gets a rank, and the
+/// pixels of each rank or below form an evenly spread pattern. Returns the
+/// ranks scaled to `[0, 1)`.
+fn void_and_cluster() -> Vec<f32> {
+    const SIGMA: f32 = 1.5;
+    let count = SIZE * SIZE;
+
+    // Gaussian energy between two pixels, indexed by their wrapped offset
+    let kernel: Vec<f32> = (0..count)
+        .map(|i| {
+            let wrap = |d: usize| d.min(SIZE - d) as f32;
+            let (dx, dy) = (wrap(i % SIZE), wrap(i / SIZE));
+            (-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA)).exp()
+        })
+        .collect();
+    let splat = |energy: &mut [f32], p: usize, sign: f32| {
+        let (px, py) = (p % SIZE, p / SIZE);
+        for (q, e) in energy.iter_mut().enumerate() {
+            let dx = (q % SIZE + SIZE - px) % SIZE;
+            let dy = (q / SIZE + SIZE - py) % SIZE;
+            *e += sign * kernel[dy * SIZE + dx];
+        }
+    };
+    let extreme = |energy: &[f32], pattern: &[bool], set: bool, tightest: bool| {
+        (0..count)
+            .filter(|&i| pattern[i] == set)
+            .max_by(|&a, &b| {
+                let order = energy[a].total_cmp(&energy[b]);
+                if tightest { order } else { order.reverse() }
+            })
+            .expect("pattern has pixels of both kinds")
+    };
+
+    // Random initial pattern, relaxed by moving the point in the tightest
+    // cluster to the largest void until that stops changing anything
+    let mut rng = Pcg::new(0x6c07_8965);
+    let initial = count / 10;
+    let mut pattern = vec![false; count];
+    let mut energy = vec![0.0; count];
+    let mut placed = 0;
+    while placed < initial {
+        let p = (rng.next_1d() * count as f32) as usize % count;
+        if !pattern[p] {
+            pattern[p] = true;
+            splat(&mut energy, p, 1.0);
+            placed += 1;
+        }
+    }
+    for _ in 0..count {
+        let cluster = extreme(&energy, &pattern, true, true);
+        pattern[cluster] = false;
+        splat(&mut energy, cluster, -1.0);
+        let void = extreme(&energy, &pattern, false, false);
+        pattern[void] = true;
+        splat(&mut energy, void, 1.0);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut rank = vec![0; count];
+    // Ranks below the initial pattern, removing its tightest clusters
+    let mut shrinking = pattern.clone();
+    let mut shrinking_energy = energy.clone();
+    for r in (0..initial).rev() {
+        let cluster = extreme(&shrinking_energy, &shrinking, true, true);
+        shrinking[cluster] = false;
+        splat(&mut shrinking_energy, cluster, -1.0);
+        rank[cluster] = r;
+    }
+    // Ranks above it, filling the largest voids. The energy of the empty
+    // pixels is the complement of this one, so once more than half are
+    // filled this also picks the tightest cluster of the empty ones.
+    for r in initial..count {
+        let void = extreme(&energy, &pattern, false, false);
+        pattern[void] = true;
+        splat(&mut energy, void, 1.0);
+        rank[void] = r;
+    }
+
+    rank.iter()
+        .map(|&r| (r as f32 + 0.5) / count as f32)
+        .collect()
+}
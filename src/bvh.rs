@@ -0,0 +1,241 @@
+use crate::aabb::Aabb;
+use crate::hittable::HitRecord;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+const BUCKET_COUNT: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+const MAX_DEPTH: usize = 64;
+const TRAVERSAL_COST: f32 = 0.125;
+
+/// Node of the flattened tree. Interior nodes store their second child in
+/// `offset`, the first child always follows the parent in the array. Leaves
+/// store the first entry of `Bvh::indices` they own in `offset`.
+#[derive(Debug, Clone, Copy)]
+struct BvhNode {
+    bounds: Aabb,
+    offset: u32,
+    count: u32,
+    axis: u8,
+}
+
+struct BuildPrimitive {
+    index: u32,
+    bounds: Aabb,
+    centroid: Vec3,
+}
+
+#[derive(Clone, Copy)]
+struct Bucket {
+    count: usize,
+    bounds: Aabb,
+}
+
+/// Bounding volume hierarchy over primitives identified by their index in the
+/// slice of bounds passed to `Bvh::new`.
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    indices: Vec<u32>,
+}
+
+impl Bvh {
+    pub fn new(bounds: &[Aabb]) -> Bvh {
+        let mut prims: Vec<BuildPrimitive> = bounds
+            .iter()
+            .enumerate()
+            .map(|(i, b)| BuildPrimitive {
+                index: i as u32,
+                bounds: *b,
+                centroid: b.centroid(),
+            })
+            .collect();
+        let mut bvh = Bvh {
+            nodes: Vec::with_capacity(2 * bounds.len().max(1)),
+            indices: Vec::with_capacity(bounds.len()),
+        };
+        if !prims.is_empty() {
+            bvh.build(&mut prims, 0);
+        }
+        bvh
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::EMPTY, |n| n.bounds)
+    }
+
+    fn build(&mut self, prims: &mut [BuildPrimitive], depth: usize) -> usize {
+        let node_index = self.nodes.len();
+        let bounds = prims.iter().fold(Aabb::EMPTY, |acc, p| acc.union(p.bounds));
+        self.nodes.push(BvhNode {
+            bounds,
+            offset: 0,
+            count: 0,
+            axis: 0,
+        });
+
+        let split = if prims.len() <= 1 || depth >= MAX_DEPTH {
+            None
+        } else {
+            find_split(prims, bounds)
+        };
+
+        match split {
+            Some((axis, mid)) => {
+                self.build(&mut prims[..mid], depth + 1);
+                let second = self.build(&mut prims[mid..], depth + 1);
+                let node = &mut self.nodes[node_index];
+                node.offset = second as u32;
+                node.axis = axis as u8;
+            }
+            None => {
+                let node = &mut self.nodes[node_index];
+                node.offset = self.indices.len() as u32;
+                node.count = prims.len() as u32;
+                self.indices.extend(prims.iter().map(|p| p.index));
+            }
+        }
+        node_index
+    }
+
+    /// Walks the tree front to back, calling `hit_primitive` with the index of
+    /// every primitive whose leaf the ray reaches and the closest distance
+    /// found so far.
+    pub fn traverse<'a, F>(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+        mut hit_primitive: F,
+    ) -> Option<HitRecord<'a>>
+    where
+        F: FnMut(usize, Ray, f32, f32) -> Option<HitRecord<'a>>,
+    {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let inv_dir = Vec3::new(
+            1.0 / r.direction.x(),
+            1.0 / r.direction.y(),
+            1.0 / r.direction.z(),
+        );
+        let dir_is_neg = [inv_dir.x() < 0.0, inv_dir.y() < 0.0, inv_dir.z() < 0.0];
+
+        let mut closest_so_far = t_max;
+        let mut hit_anything: Option<HitRecord> = None;
+        let mut stack = [0usize; MAX_DEPTH + 1];
+        let mut stack_len = 0;
+        let mut current = 0;
+
+        loop {
+            let node = &self.nodes[current];
+            if node
+                .bounds
+                .hit(r.origin, inv_dir, t_min, closest_so_far)
+                .is_some()
+            {
+                if node.count > 0 {
+                    let first = node.offset as usize;
+                    for &index in &self.indices[first..first + node.count as usize] {
+                        if let Some(hit) = hit_primitive(index as usize, r, t_min, closest_so_far) {
+                            closest_so_far = hit.t;
+                            hit_anything = Some(hit);
+                        }
+                    }
+                } else {
+                    // Visit the child closer to the ray origin first
+                    let (near, far) = if dir_is_neg[node.axis as usize] {
+                        (node.offset as usize, current + 1)
+                    } else {
+                        (current + 1, node.offset as usize)
+                    };
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    current = near;
+                    continue;
+                }
+            }
+            if stack_len == 0 {
+                break;
+            }
+            stack_len -= 1;
+            current = stack[stack_len];
+        }
+        hit_anything
+    }
+}
+
+/// Binned surface area heuristic. Returns the split axis and the partition
+/// point of `prims`, or `None` when a leaf is cheaper.
+fn find_split(prims: &mut [BuildPrimitive], bounds: Aabb) -> Option<(usize, usize)> {
+    let centroid_bounds = prims
+        .iter()
+        .fold(Aabb::EMPTY, |acc, p| acc.grow(p.centroid));
+    let extent = centroid_bounds.extent();
+    let leaf_cost = prims.len() as f32;
+    let inv_area = 1.0 / bounds.surface_area().max(f32::MIN_POSITIVE);
+
+    let mut best: Option<(usize, usize, f32)> = None;
+    for axis in 0..3 {
+        if extent[axis] <= 0.0 {
+            continue;
+        }
+        let mut buckets = [Bucket {
+            count: 0,
+            bounds: Aabb::EMPTY,
+        }; BUCKET_COUNT];
+        for p in prims.iter() {
+            let b = &mut buckets[bucket_index(p.centroid, axis, centroid_bounds)];
+            b.count += 1;
+            b.bounds = b.bounds.union(p.bounds);
+        }
+
+        // Sweep from the right to get the cost of every right-hand side
+        let mut right_area = [0.0f32; BUCKET_COUNT];
+        let mut right_count = [0usize; BUCKET_COUNT];
+        let mut acc_bounds = Aabb::EMPTY;
+        let mut acc_count = 0;
+        for i in (1..BUCKET_COUNT).rev() {
+            acc_bounds = acc_bounds.union(buckets[i].bounds);
+            acc_count += buckets[i].count;
+            right_area[i] = acc_bounds.surface_area();
+            right_count[i] = acc_count;
+        }
+
+        let mut acc_bounds = Aabb::EMPTY;
+        let mut acc_count = 0;
+        for i in 0..BUCKET_COUNT - 1 {
+            acc_bounds = acc_bounds.union(buckets[i].bounds);
+            acc_count += buckets[i].count;
+            if acc_count == 0 || right_count[i + 1] == 0 {
+                continue;
+            }
+            let cost = TRAVERSAL_COST
+                + (acc_count as f32 * acc_bounds.surface_area()
+                    + right_count[i + 1] as f32 * right_area[i + 1])
+                    * inv_area;
+            if best.is_none_or(|(_, _, c)| cost < c) {
+                best = Some((axis, i, cost));
+            }
+        }
+    }
+
+    let (axis, split_bucket, cost) = best?;
+    if prims.len() <= MAX_LEAF_SIZE && cost >= leaf_cost {
+        return None;
+    }
+
+    let mut mid = 0;
+    for i in 0..prims.len() {
+        if bucket_index(prims[i].centroid, axis, centroid_bounds) <= split_bucket {
+            prims.swap(i, mid);
+            mid += 1;
+        }
+    }
+    Some((axis, mid))
+}
+
+#[inline]
+fn bucket_index(c: Vec3, axis: usize, centroid_bounds: Aabb) -> usize {
+    let offset = (c[axis] - centroid_bounds.min[axis]) / centroid_bounds.extent()[axis];
+    ((BUCKET_COUNT as f32 * offset) as usize).min(BUCKET_COUNT - 1)
+}
@@ -1,6 +1,53 @@
 use crate::ray::Ray;
+use crate::sampler::{Sampler, Samplers};
 use crate::vec3::Vec3;
 
+/// Parameters a `Camera` is built from, kept around so the image aspect ratio
+/// can be chosen independently of the scene.
+#[derive(Debug, Clone, Copy)]
+pub struct CameraSettings {
+    pub lookfrom: Vec3,
+    pub lookat: Vec3,
+    pub vup: Vec3,
+    pub vfov: f32,
+    pub aperture: f32,
+    pub focus_dist: f32,
+    /// Times the shutter opens and closes; rays are spread uniformly between
+    /// them.
+    pub shutter_open: f32,
+    pub shutter_close: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> CameraSettings {
+        CameraSettings {
+            lookfrom: Vec3::new(13.0, 2.0, 3.0),
+            lookat: Vec3::new(0.0, 0.0, 0.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: 20.0,
+            aperture: 0.1,
+            focus_dist: 10.0,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+        }
+    }
+}
+
+impl CameraSettings {
+    pub fn build(&self, aspect_ratio: f32) -> Camera {
+        Camera::new(
+            self.lookfrom,
+            self.lookat,
+            self.vup,
+            self.vfov,
+            aspect_ratio,
+            self.aperture,
+            self.focus_dist,
+        )
+        .with_shutter(self.shutter_open, self.shutter_close)
+    }
+}
+
 pub struct Camera {
     origin: Vec3,
     lower_left_corner: Vec3,
@@ -8,8 +55,10 @@ pub struct Camera {
     vertical: Vec3,
     u: Vec3,
     v: Vec3,
-    // w: Vec3,
+    w: Vec3,
     lens_radius: f32,
+    time0: f32,
+    time1: f32,
 }
 
 impl Camera {
@@ -43,17 +92,36 @@ impl Camera {
             vertical,
             u,
             v,
-            // w,
+            w,
             lens_radius,
+            time0: 0.0,
+            time1: 0.0,
         }
     }
 
-    pub fn get_ray(&self, s: f32, t: f32) -> Ray {
-        let rd = self.lens_radius * Vec3::random_in_unit_disk();
+    pub fn with_shutter(mut self, open: f32, close: f32) -> Camera {
+        self.time0 = open;
+        self.time1 = close;
+        self
+    }
+
+    /// Distance of `p` in front of the camera, along its viewing direction.
+    pub fn depth(&self, p: Vec3) -> f32 {
+        Vec3::dot(self.origin - p, self.w)
+    }
+
+    pub fn get_ray(&self, s: f32, t: f32, sampler: &mut Samplers) -> Ray {
+        let rd = self.lens_radius * Vec3::random_in_unit_disk(sampler);
         let offset = self.u * rd.x() + self.v * rd.y();
+        let time = if self.time1 > self.time0 {
+            sampler.range(self.time0, self.time1)
+        } else {
+            self.time0
+        };
         Ray::new(
             self.origin + offset,
             self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
+            time,
         )
     }
 }
@@ -0,0 +1,219 @@
+use crate::aov::Aov;
+use crate::camera::CameraSettings;
+use crate::denoise::Denoiser;
+use crate::progressive;
+use crate::sampler::SamplerKind;
+use crate::scene::ImageSettings;
+use crate::tile::{TileOrder, Tiling};
+use crate::tonemap::ToneMapper;
+
+use clap::Parser;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Path tracer based on "Ray Tracing in One Weekend".
+///
+/// Image and camera options override the values of the selected scene.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Args {
+    /// Built-in scene name (random, cornell) or path to a scene file
+    #[arg(short, long, default_value = "random")]
+    pub scene: String,
+
+    /// Image width in pixels, at least 2
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(2..))]
+    pub width: Option<u32>,
+
+    /// Image width divided by image height
+    #[arg(short, long)]
+    pub aspect_ratio: Option<f32>,
+
+    /// Samples per pixel, the most any pixel gets with adaptive sampling
+    #[arg(short = 'n', long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub samples: Option<u32>,
+
+    /// Stop sampling a pixel once the standard error of its luminance falls
+    /// below this fraction of the luminance; 0 disables adaptive sampling
+    #[arg(long, value_name = "ERROR", value_parser = |v: &str| number(v, Bound::NonNegative))]
+    pub threshold: Option<f32>,
+
+    /// Samples every pixel takes before adaptive sampling may stop it
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub min_samples: Option<u32>,
+
+    /// Sample pattern; low-discrepancy samplers converge faster than
+    /// independent random numbers
+    #[arg(long, value_enum)]
+    pub sampler: Option<SamplerKind>,
+
+    /// Exposure adjustment in stops for 8-bit outputs and the window
+    #[arg(
+        long,
+        value_name = "EV",
+        allow_negative_numbers = true,
+        value_parser = |v: &str| number(v, Bound::Any)
+    )]
+    pub exposure: Option<f32>,
+
+    /// Curve mapping radiance to display values for 8-bit outputs and the
+    /// window
+    #[arg(long, value_enum)]
+    pub tonemap: Option<ToneMapper>,
+
+    /// Radiance that extended Reinhard maps to white
+    #[arg(long, value_name = "RADIANCE", value_parser = |v: &str| number(v, Bound::Positive))]
+    pub white: Option<f32>,
+
+    /// Maximum number of bounces per path
+    #[arg(short = 'd', long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_depth: Option<u32>,
+
+    /// Camera lens diameter, 0 for a pinhole camera
+    #[arg(long)]
+    pub aperture: Option<f32>,
+
+    /// Distance from the camera to the plane in focus
+    #[arg(long)]
+    pub focus_dist: Option<f32>,
+
+    /// Seed for scene generation and sampling, random when omitted
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Image file to write, by extension 8-bit PNG or PPM, or linear float
+    /// PFM, HDR or EXR; can be repeated.
+    /// Headless renders default to render.png and render.ppm
+    #[arg(short, long = "output", value_name = "PATH")]
+    pub outputs: Vec<PathBuf>,
+
+    /// Image of how many samples each pixel took, for debugging adaptive
+    /// sampling
+    #[arg(long, value_name = "PATH")]
+    pub sample_map: Option<PathBuf>,
+
+    /// Render passes to write next to each output: as extra channels of EXR
+    /// files, as `<stem>.<pass>.<ext>` files otherwise. Depth and position
+    /// are infinite where rays escape to the background
+    #[arg(long = "aov", value_name = "PASS", value_enum, value_delimiter = ',')]
+    pub aovs: Vec<Aov>,
+
+    /// Denoise the finished image with this many filter passes, guided by
+    /// the albedo, normal and depth of the first hits
+    #[arg(
+        long,
+        value_name = "PASSES",
+        num_args = 0..=1,
+        default_missing_value = "5",
+        value_parser = clap::value_parser!(u32).range(1..=10)
+    )]
+    pub denoise: Option<u32>,
+
+    /// Width and height in pixels of the tiles the image is rendered in
+    #[arg(long, default_value_t = 32, value_parser = clap::value_parser!(u32).range(1..))]
+    pub tile_size: u32,
+
+    /// Order tiles are rendered in
+    #[arg(long, value_enum, default_value_t = TileOrder::Spiral)]
+    pub tile_order: TileOrder,
+
+    /// Render without opening a window
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Refine the whole image one sample per pixel at a time instead of
+    /// finishing each pixel before moving on
+    #[arg(long)]
+    pub progressive: bool,
+
+    /// Stop a progressive render after this many seconds, even if it has
+    /// fewer samples per pixel than requested
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        requires = "progressive",
+        value_parser = |v: &str| number(v, Bound::Positive)
+    )]
+    pub time_limit: Option<f32>,
+}
+
+impl Args {
+    /// Stopping condition of a progressive render, `None` when not
+    /// rendering progressively.
+    pub fn budget(&self, image: &ImageSettings) -> Option<progressive::Budget> {
+        self.progressive.then(|| progressive::Budget {
+            samples: image.samples_per_pixel,
+            time: self.time_limit.map(Duration::from_secs_f32),
+        })
+    }
+
+    pub fn tiling(&self) -> Tiling {
+        Tiling {
+            size: self.tile_size,
+            order: self.tile_order,
+        }
+    }
+
+    pub fn denoiser(&self) -> Option<Denoiser> {
+        self.denoise.map(|iterations| Denoiser { iterations })
+    }
+
+    pub fn apply(&self, image: &mut ImageSettings, camera: &mut CameraSettings) {
+        if let Some(width) = self.width {
+            image.width = width;
+        }
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            image.aspect_ratio = aspect_ratio;
+        }
+        if let Some(samples) = self.samples {
+            image.samples_per_pixel = samples;
+        }
+        if let Some(threshold) = self.threshold {
+            image.adaptive_threshold = threshold;
+        }
+        if let Some(min_samples) = self.min_samples {
+            image.min_samples = min_samples;
+        }
+        if let Some(sampler) = self.sampler {
+            image.sampler = sampler;
+        }
+        if let Some(exposure) = self.exposure {
+            image.display.exposure = exposure;
+        }
+        if let Some(tonemap) = self.tonemap {
+            image.display.tone_mapper = tonemap;
+        }
+        if let Some(white) = self.white {
+            image.display.white = white;
+        }
+        if let Some(max_depth) = self.max_depth {
+            image.max_depth = max_depth;
+        }
+        if let Some(aperture) = self.aperture {
+            camera.aperture = aperture;
+        }
+        if let Some(focus_dist) = self.focus_dist {
+            camera.focus_dist = focus_dist;
+        }
+    }
+}
+
+/// Values a number argument may take besides being finite.
+#[derive(Clone, Copy)]
+enum Bound {
+    Any,
+    NonNegative,
+    Positive,
+}
+
+fn number(value: &str, bound: Bound) -> Result<f32, String> {
+    let (valid, kind): (fn(f32) -> bool, &str) = match bound {
+        Bound::Any => (|_| true, "a finite"),
+        Bound::NonNegative => (|n| n >= 0.0, "a non-negative"),
+        Bound::Positive => (|n| n > 0.0, "a positive"),
+    };
+    match value.parse::<f32>() {
+        Ok(number) if number.is_finite() && valid(number) => Ok(number),
+        _ => Err(format!("'{}' is not {} number", value, kind)),
+    }
+}
@@ -0,0 +1,119 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Materials;
+use crate::ray::Ray;
+use crate::sampler::Samplers;
+use crate::vec3::Vec3;
+
+/// Box with arbitrary orientation, intersected as a slab test in its own
+/// frame.
+pub struct Cuboid {
+    center: Vec3,
+    /// Unit edge directions of the box.
+    axes: [Vec3; 3],
+    half_size: Vec3,
+    mat: Materials,
+}
+
+impl Cuboid {
+    /// Axis-aligned box with opposite corners `a` and `b`.
+    pub fn new(a: Vec3, b: Vec3, m: Materials) -> Cuboid {
+        let bounds = Aabb::new(a, b);
+        Cuboid::oriented(bounds.centroid(), bounds.extent(), Vec3::ZERO, m)
+    }
+
+    /// Box of `size` centered on `center`, rotated by `rotation` degrees
+    /// around the x, y and z axes, in that order.
+    pub fn oriented(center: Vec3, size: Vec3, rotation: Vec3, m: Materials) -> Cuboid {
+        Cuboid {
+            center,
+            axes: [
+                rotate(Vec3::new(1.0, 0.0, 0.0), rotation),
+                rotate(Vec3::new(0.0, 1.0, 0.0), rotation),
+                rotate(Vec3::new(0.0, 0.0, 1.0), rotation),
+            ],
+            half_size: 0.5 * size,
+            mat: m,
+        }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut Samplers,
+    ) -> Option<HitRecord<'_>> {
+        let offset = r.origin - self.center;
+        let mut t_near = f32::NEG_INFINITY;
+        let mut t_far = f32::INFINITY;
+        for axis in 0..3 {
+            let origin = Vec3::dot(offset, self.axes[axis]);
+            let inv_d = 1.0 / Vec3::dot(r.direction, self.axes[axis]);
+            let t0 = (-self.half_size[axis] - origin) * inv_d;
+            let t1 = (self.half_size[axis] - origin) * inv_d;
+            t_near = t_near.max(t0.min(t1));
+            t_far = t_far.min(t0.max(t1));
+        }
+        if t_near > t_far {
+            return None;
+        }
+        // Rays starting inside the box hit it on the way out
+        let t = if t_near >= t_min { t_near } else { t_far };
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        // The face is the axis on which the hit point is furthest out,
+        // relative to the box size
+        let p = r.at(t) - self.center;
+        let local = [0, 1, 2].map(|axis| Vec3::dot(p, self.axes[axis]) / self.half_size[axis]);
+        let face = (0..3)
+            .max_by(|&a, &b| local[a].abs().total_cmp(&local[b].abs()))
+            .unwrap_or(0);
+        let outward_normal = self.axes[face] * local[face].signum();
+        let uv = (
+            0.5 * (local[(face + 1) % 3] + 1.0),
+            0.5 * (local[(face + 2) % 3] + 1.0),
+        );
+        Some(HitRecord::new(r, t, outward_normal, uv, &self.mat))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let extent = Vec3::new(
+            (0..3)
+                .map(|i| (self.axes[i].x() * self.half_size[i]).abs())
+                .sum(),
+            (0..3)
+                .map(|i| (self.axes[i].y() * self.half_size[i]).abs())
+                .sum(),
+            (0..3)
+                .map(|i| (self.axes[i].z() * self.half_size[i]).abs())
+                .sum(),
+        );
+        Aabb::new(self.center - extent, self.center + extent).pad(1e-4)
+    }
+}
+
+fn rotate(v: Vec3, degrees: Vec3) -> Vec3 {
+    let (sin_x, cos_x) = degrees.x().to_radians().sin_cos();
+    let (sin_y, cos_y) = degrees.y().to_radians().sin_cos();
+    let (sin_z, cos_z) = degrees.z().to_radians().sin_cos();
+    let v = Vec3::new(
+        v.x(),
+        cos_x * v.y() - sin_x * v.z(),
+        sin_x * v.y() + cos_x * v.z(),
+    );
+    let v = Vec3::new(
+        cos_y * v.x() + sin_y * v.z(),
+        v.y(),
+        -sin_y * v.x() + cos_y * v.z(),
+    );
+    Vec3::new(
+        cos_z * v.x() - sin_z * v.y(),
+        sin_z * v.x() + cos_z * v.y(),
+        v.z(),
+    )
+}
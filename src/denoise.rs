@@ -0,0 +1,144 @@
+use crate::adaptive::PixelEstimate;
+use crate::aov::Aovs;
+use crate::vec3::Vec3;
+
+use rayon::prelude::*;
+
+/// Edge-avoiding à-trous wavelet filter (Dammertz et al.), with the color
+/// weights scaled by the per-pixel variance as in SVGF (Schied et al.).
+/// It is guided by the albedo, normal and depth passes, and lighting is
+/// filtered with the albedo divided out, so textures stay sharp.
+#[derive(Debug, Clone, Copy)]
+pub struct Denoiser {
+    /// Filter passes; each doubles the spacing between taps, so the
+    /// footprint grows to about `4 << iterations` pixels.
+    pub iterations: u32,
+}
+
+/// Tap weights of the B3 spline, for offsets 0, 1 and 2.
+const KERNEL: [f32; 3] = [3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+const SIGMA_LUMINANCE: f32 = 4.0;
+const SIGMA_ALBEDO: f32 = 0.2;
+const SIGMA_DEPTH: f32 = 0.05;
+const NORMAL_EXPONENT: i32 = 128;
+
+impl Denoiser {
+    /// Denoised colors of `pixels`, stored row by row.
+    pub fn denoise(&self, width: u32, height: u32, pixels: &[PixelEstimate]) -> Vec<Vec3> {
+        let (width, height) = (width as usize, height as usize);
+        let guides: Vec<&Aovs> = pixels.iter().map(|p| p.aovs()).collect();
+        let albedo: Vec<Vec3> = guides.iter().map(|g| demodulation(g.albedo)).collect();
+        let mut color: Vec<Vec3> = pixels
+            .iter()
+            .zip(&albedo)
+            .map(|(p, a)| p.mean() / *a)
+            .collect();
+        let variance: Vec<f32> = pixels
+            .iter()
+            .zip(&albedo)
+            .map(|(p, a)| {
+                let variance = p.variance();
+                // A single sample says nothing about the noise, so let the
+                // geometry guides do all the work
+                let variance = if variance.is_finite() { variance } else { 1e6 };
+                variance / a.luminance().max(1e-3).powi(2)
+            })
+            .collect();
+        let mut variance = blur_variance(width, height, &variance);
+
+        for iteration in 0..self.iterations {
+            let step = 1 << iteration;
+            let (next_color, next_variance): (Vec<Vec3>, Vec<f32>) = (0..width * height)
+                .into_par_iter()
+                .map(|p| {
+                    let (x, y) = (p % width, p / width);
+                    let luminance = color[p].luminance();
+                    let sigma = SIGMA_LUMINANCE * variance[p].max(0.0).sqrt() + 1e-4;
+                    let mut sum = Vec3::ZERO;
+                    let mut sum_variance = 0.0;
+                    let mut total = 0.0;
+                    for dy in -2..=2_i32 {
+                        for dx in -2..=2_i32 {
+                            let qx = x as i64 + (dx * step) as i64;
+                            let qy = y as i64 + (dy * step) as i64;
+                            if qx < 0 || qy < 0 || qx >= width as i64 || qy >= height as i64 {
+                                continue;
+                            }
+                            let q = qy as usize * width + qx as usize;
+                            let h = KERNEL[dx.unsigned_abs() as usize]
+                                * KERNEL[dy.unsigned_abs() as usize];
+                            let w_luminance =
+                                (-(luminance - color[q].luminance()).abs() / sigma).exp();
+                            let w = h
+                                * w_luminance
+                                * edge_weight(guides[p], guides[q], albedo[p], albedo[q], step);
+                            sum += w * color[q];
+                            sum_variance += w * w * variance[q];
+                            total += w;
+                        }
+                    }
+                    // The center tap always has full weight, so `total` > 0
+                    (sum / total, sum_variance / (total * total))
+                })
+                .unzip();
+            color = next_color;
+            variance = next_variance;
+        }
+
+        color.iter().zip(&albedo).map(|(c, a)| *c * *a).collect()
+    }
+}
+
+/// Albedo the lighting is divided by, kept away from zero so black surfaces
+/// don't blow up the noise.
+fn demodulation(albedo: Vec3) -> Vec3 {
+    albedo.map(|a| a.max(0.01))
+}
+
+/// How much the guides of pixels `p` and `q` say they show the same surface.
+fn edge_weight(p: &Aovs, q: &Aovs, albedo_p: Vec3, albedo_q: Vec3, step: i32) -> f32 {
+    let w_normal = if p.normal.near_zero() && q.normal.near_zero() {
+        1.0
+    } else {
+        Vec3::dot(p.normal.unit(), q.normal.unit())
+            .max(0.0)
+            .powi(NORMAL_EXPONENT)
+    };
+    let w_depth = if p.depth.is_infinite() || q.depth.is_infinite() {
+        // Background only blends with background
+        if p.depth == q.depth { 1.0 } else { 0.0 }
+    } else {
+        let depth_scale = SIGMA_DEPTH * step as f32 * p.depth + 1e-4;
+        (-(p.depth - q.depth).abs() / depth_scale).exp()
+    };
+    let w_albedo = (-(albedo_p - albedo_q).length_squared() / (SIGMA_ALBEDO * SIGMA_ALBEDO)).exp();
+    w_normal * w_depth * w_albedo
+}
+
+/// 3x3 Gaussian blur of the variance, which is too noisy to steer the
+/// filter when estimated from few samples.
+fn blur_variance(width: usize, height: usize, variance: &[f32]) -> Vec<f32> {
+    const WEIGHTS: [f32; 2] = [1.0 / 2.0, 1.0 / 4.0];
+    (0..width * height)
+        .into_par_iter()
+        .map(|p| {
+            let (x, y) = (p % width, p / width);
+            let mut sum = 0.0;
+            let mut total = 0.0;
+            for dy in -1..=1_i64 {
+                for dx in -1..=1_i64 {
+                    let (qx, qy) = (x as i64 + dx, y as i64 + dy);
+                    if qx < 0 || qy < 0 || qx >= width as i64 || qy >= height as i64 {
+                        continue;
+                    }
+                    let w =
+                        WEIGHTS[dx.unsigned_abs() as usize] * WEIGHTS[dy.unsigned_abs() as usize];
+                    sum += w * variance[qy as usize * width + qx as usize];
+                    total += w;
+                }
+            }
+            sum / total
+        })
+        .collect()
+}
@@ -0,0 +1,90 @@
+/// Piecewise-constant density over `[0, 1)` built from non-negative weights.
+pub struct Distribution1D {
+    func: Vec<f32>,
+    /// Running sum of `func`, normalized so the last entry is 1.
+    cdf: Vec<f32>,
+    /// Average of `func`, which normalizes it to a density.
+    integral: f32,
+}
+
+impl Distribution1D {
+    pub fn new(func: Vec<f32>) -> Distribution1D {
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 0..n {
+            cdf[i + 1] = cdf[i] + func[i] / n as f32;
+        }
+        let integral = cdf[n];
+        for (i, value) in cdf.iter_mut().enumerate() {
+            // All-zero weights fall back to a uniform distribution
+            *value = if integral > 0.0 {
+                *value / integral
+            } else {
+                i as f32 / n as f32
+            };
+        }
+        Distribution1D {
+            func,
+            cdf,
+            integral,
+        }
+    }
+
+    /// Maps a uniform `u` to a point in `[0, 1)`, returning it together with
+    /// its density and the index of the segment it falls in.
+    pub fn sample(&self, u: f32) -> (f32, f32, usize) {
+        let n = self.func.len();
+        let index = self.cdf.partition_point(|&c| c <= u).clamp(1, n) - 1;
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let offset = if width > 0.0 {
+            (u - self.cdf[index]) / width
+        } else {
+            0.0
+        };
+        let x = ((index as f32 + offset) / n as f32).min(1.0 - f32::EPSILON);
+        (x, self.pdf(index), index)
+    }
+
+    /// Density of segment `index`.
+    pub fn pdf(&self, index: usize) -> f32 {
+        if self.integral > 0.0 {
+            self.func[index] / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Density over `[0, 1)²` sampled row first, then column within the row.
+pub struct Distribution2D {
+    rows: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /// `func` holds `width * height` weights, row by row.
+    pub fn new(func: &[f32], width: usize, height: usize) -> Distribution2D {
+        let rows: Vec<Distribution1D> = func
+            .chunks_exact(width)
+            .take(height)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(rows.iter().map(|row| row.integral).collect());
+        Distribution2D { rows, marginal }
+    }
+
+    /// Maps two uniform numbers to a point `(x, y)` and its density.
+    pub fn sample(&self, u: f32, v: f32) -> ((f32, f32), f32) {
+        let (y, pdf_y, row) = self.marginal.sample(v);
+        let (x, pdf_x, _) = self.rows[row].sample(u);
+        ((x, y), pdf_x * pdf_y)
+    }
+
+    pub fn pdf(&self, x: f32, y: f32) -> f32 {
+        let height = self.rows.len();
+        let row = ((y * height as f32) as usize).min(height - 1);
+        let width = self.rows[row].func.len();
+        let column = ((x * width as f32) as usize).min(width - 1);
+        self.marginal.pdf(row) * self.rows[row].pdf(column)
+    }
+}
@@ -0,0 +1,101 @@
+use crate::distribution::Distribution2D;
+use crate::sampler::{Sampler, Samplers};
+use crate::vec3::Vec3;
+
+use anyhow::Context;
+use std::f32::consts::PI;
+use std::path::Path;
+
+/// Equirectangular environment image, importance sampled by luminance.
+///
+/// The center of the image looks down -Z, the top row is straight up and
+/// `u` increases clockwise seen from above.
+pub struct EnvironmentMap {
+    width: u32,
+    height: u32,
+    pixels: Vec<Vec3>,
+    /// Rotation around the Y axis, as sine and cosine.
+    rotation: (f32, f32),
+    intensity: f32,
+    distribution: Distribution2D,
+}
+
+impl EnvironmentMap {
+    /// Loads a Radiance HDR or OpenEXR image, rotated by `rotation` degrees
+    /// around the Y axis and scaled by `intensity`.
+    pub fn load(path: &Path, rotation: f32, intensity: f32) -> anyhow::Result<EnvironmentMap> {
+        let image = image::open(path)
+            .with_context(|| format!("Failed to load environment {}", path.display()))?
+            .into_rgb32f();
+        let (width, height) = image.dimensions();
+        let pixels: Vec<Vec3> = image
+            .pixels()
+            .map(|p| Vec3::new(p[0], p[1], p[2]))
+            .collect();
+
+        // Rows near the poles cover less solid angle
+        let weights: Vec<f32> = pixels
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let row = i / width as usize;
+                let sin_theta = (PI * (row as f32 + 0.5) / height as f32).sin();
+                p.luminance().max(0.0) * sin_theta
+            })
+            .collect();
+        let distribution = Distribution2D::new(&weights, width as usize, height as usize);
+
+        Ok(EnvironmentMap {
+            width,
+            height,
+            pixels,
+            rotation: rotation.to_radians().sin_cos(),
+            intensity,
+            distribution,
+        })
+    }
+
+    pub fn color(&self, direction: Vec3) -> Vec3 {
+        let (u, v) = self.direction_to_uv(direction);
+        let x = ((u * self.width as f32) as u32).min(self.width - 1);
+        let y = ((v * self.height as f32) as u32).min(self.height - 1);
+        self.intensity * self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// Solid angle density of `sample` picking `direction`.
+    pub fn pdf_value(&self, direction: Vec3) -> f32 {
+        let (u, v) = self.direction_to_uv(direction);
+        let sin_theta = (PI * v).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+
+    pub fn sample(&self, sampler: &mut Samplers) -> Vec3 {
+        let (u, v) = sampler.next_2d();
+        let ((u, v), _) = self.distribution.sample(u, v);
+        self.uv_to_direction(u, v)
+    }
+
+    fn direction_to_uv(&self, direction: Vec3) -> (f32, f32) {
+        let d = direction.unit();
+        let (sin, cos) = self.rotation;
+        // Undo the map rotation
+        let x = cos * d.x() - sin * d.z();
+        let z = sin * d.x() + cos * d.z();
+        let u = 0.5 + x.atan2(-z) / (2.0 * PI);
+        let v = d.y().clamp(-1.0, 1.0).acos() / PI;
+        (u, v)
+    }
+
+    fn uv_to_direction(&self, u: f32, v: f32) -> Vec3 {
+        let theta = PI * v;
+        let phi = 2.0 * PI * (u - 0.5);
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let x = sin_theta * phi.sin();
+        let z = -sin_theta * phi.cos();
+        let (sin, cos) = self.rotation;
+        Vec3::new(cos * x + sin * z, cos_theta, -sin * x + cos * z)
+    }
+}
@@ -0,0 +1,167 @@
+use crate::adaptive::PixelEstimate;
+use crate::tile::{Tile, Tiling};
+use crate::vec3::Vec3;
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Pixel estimates of a whole image, which render workers fill in a tile at
+/// a time while the window reads them. Every tile has its own lock, so
+/// workers only ever wait for the window to copy the colors of the tile
+/// they are about to write.
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    /// Tiles in rendering order.
+    tiles: Vec<Tile>,
+    buffers: Vec<Mutex<TileBuffer>>,
+    /// Incremented by `clear`; buffers from an older generation read as
+    /// empty.
+    generation: AtomicU64,
+}
+
+/// Pixels of one tile, row by row.
+struct TileBuffer {
+    generation: u64,
+    estimates: Vec<PixelEstimate>,
+    /// Means of `estimates`, which is all the window needs.
+    colors: Vec<Vec3>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32, tiling: Tiling) -> Framebuffer {
+        let tiles = tiling.tiles(width, height);
+        let buffers = tiles
+            .iter()
+            .map(|tile| {
+                Mutex::new(TileBuffer {
+                    generation: 0,
+                    estimates: vec![PixelEstimate::default(); tile.area()],
+                    colors: vec![Vec3::ZERO; tile.area()],
+                })
+            })
+            .collect();
+        Framebuffer {
+            width,
+            height,
+            tiles,
+            buffers,
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn tiles(&self) -> &[Tile] {
+        &self.tiles
+    }
+
+    /// The whole image as a single tile.
+    pub fn bounds(&self) -> Tile {
+        Tile {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Empties every tile for a new render. Buffers are reset as they are
+    /// next used rather than all at once, so this is cheap enough to call
+    /// every frame while the camera moves.
+    pub fn clear(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Estimates of the pixels of tile `index`, row by row.
+    pub fn read(&self, index: usize) -> Vec<PixelEstimate> {
+        self.buffer(index, |buffer| buffer.estimates.clone())
+    }
+
+    /// Replaces the estimates of the pixels of tile `index`, given row by
+    /// row.
+    pub fn write(&self, index: usize, estimates: &[PixelEstimate]) {
+        self.buffer(index, |buffer| {
+            buffer.estimates.copy_from_slice(estimates);
+            for (color, estimate) in buffer.colors.iter_mut().zip(estimates) {
+                *color = estimate.mean();
+            }
+        })
+    }
+
+    /// Mean colors of the pixels of tile `index`, row by row.
+    pub fn colors(&self, index: usize) -> Vec<Vec3> {
+        self.buffer(index, |buffer| buffer.colors.clone())
+    }
+
+    /// Every estimate, rows from the top of the image.
+    pub fn pixels(&self) -> Vec<PixelEstimate> {
+        let mut pixels = vec![PixelEstimate::default(); (self.width * self.height) as usize];
+        for (index, tile) in self.tiles.iter().enumerate() {
+            let estimates = self.read(index);
+            for (row, chunk) in (tile.y..).zip(estimates.chunks(tile.width as usize)) {
+                let start = (row * self.width + tile.x) as usize;
+                pixels[start..start + chunk.len()].copy_from_slice(chunk);
+            }
+        }
+        pixels
+    }
+
+    /// Runs `f` on the buffer of tile `index`, emptied first if it is left
+    /// over from before the last `clear`.
+    fn buffer<T>(&self, index: usize, f: impl FnOnce(&mut TileBuffer) -> T) -> T {
+        let generation = self.generation.load(Ordering::Relaxed);
+        let mut buffer = self.buffers[index].lock().unwrap();
+        if buffer.generation != generation {
+            buffer.estimates.fill(PixelEstimate::default());
+            buffer.colors.fill(Vec3::ZERO);
+            buffer.generation = generation;
+        }
+        f(&mut buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aov::Aovs;
+    use crate::render::Sample;
+    use crate::tile::TileOrder;
+
+    fn estimate(color: Vec3) -> PixelEstimate {
+        let mut estimate = PixelEstimate::default();
+        estimate.add(Sample {
+            color,
+            aovs: Aovs::default(),
+        });
+        estimate
+    }
+
+    #[test]
+    fn tiles_land_in_place_and_clear_empties_them() {
+        let tiling = Tiling {
+            size: 4,
+            order: TileOrder::Spiral,
+        };
+        let framebuffer = Framebuffer::new(10, 6, tiling);
+        for (index, tile) in framebuffer.tiles().iter().enumerate() {
+            let estimates: Vec<PixelEstimate> = tile
+                .pixels()
+                .map(|(x, y)| estimate(Vec3::new(x as f32, y as f32, 1.0)))
+                .collect();
+            framebuffer.write(index, &estimates);
+            assert_eq!(
+                framebuffer.colors(index)[0],
+                Vec3::new(tile.x as f32, tile.y as f32, 1.0)
+            );
+        }
+        for (i, pixel) in framebuffer.pixels().iter().enumerate() {
+            assert_eq!(
+                pixel.mean(),
+                Vec3::new((i % 10) as f32, (i / 10) as f32, 1.0)
+            );
+        }
+
+        framebuffer.clear();
+        assert!(framebuffer.pixels().iter().all(|p| p.samples() == 0));
+        assert!(framebuffer.colors(0).iter().all(|c| *c == Vec3::ZERO));
+    }
+}
@@ -0,0 +1,65 @@
+use crate::sampler::{Pcg, Sampler, hash_combine, permute};
+
+/// Bases of the dimensions the Halton sampler covers; later dimensions fall
+/// back to independent random numbers.
+const PRIMES: [u32; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+/// Halton sequence with the digits of every dimension shuffled by random
+/// permutations, chosen per pixel so neighboring pixels don't repeat the
+/// same pattern.
+pub struct Halton {
+    seed: u32,
+    index: u32,
+    dimension: usize,
+    random: Pcg,
+}
+
+impl Halton {
+    pub fn new(seed: u32, index: u32, random: Pcg) -> Halton {
+        Halton {
+            seed,
+            index,
+            dimension: 0,
+            random,
+        }
+    }
+}
+
+impl Sampler for Halton {
+    fn next_1d(&mut self) -> f32 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        match PRIMES.get(dimension) {
+            Some(&base) => {
+                let seed = hash_combine(self.seed, dimension as u32);
+                scrambled_radical_inverse(base, self.index, seed)
+            }
+            None => self.random.next_1d(),
+        }
+    }
+}
+
+/// Mirrors the base `base` digits of `index` around the radix point, with
+/// every digit position permuted by its own permutation.
+fn scrambled_radical_inverse(base: u32, mut index: u32, seed: u32) -> f32 {
+    let inv_base = 1.0 / base as f64;
+    let mut factor = inv_base;
+    let mut value = 0.0;
+    let mut position = 0;
+    // Scrambling turns the implicit leading zeros into digits too, so keep
+    // going until they no longer change the float
+    while factor > 1e-9 {
+        let digit = index % base;
+        index /= base;
+        let scrambled = permute(digit, base, hash_combine(seed, position));
+        value += scrambled as f64 * factor;
+        factor *= inv_base;
+        position += 1;
+    }
+    (value as f32).min(1.0 - f32::EPSILON / 2.0)
+}
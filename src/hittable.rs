@@ -1,6 +1,17 @@
+use crate::aabb::Aabb;
+use crate::animated::Animated;
+use crate::bvh::Bvh;
+use crate::cuboid::Cuboid;
+use crate::instance::Instance;
 use crate::material::Materials;
+use crate::medium::{ConstantMedium, HeterogeneousMedium};
+use crate::mesh::Mesh;
+use crate::plane::Plane;
+use crate::quad::Quad;
 use crate::ray::Ray;
+use crate::sampler::{Sampler, Samplers};
 use crate::sphere::Sphere;
+use crate::triangle::Triangle;
 use crate::vec3::Vec3;
 
 pub struct HitRecord<'a> {
@@ -8,53 +19,304 @@ pub struct HitRecord<'a> {
     pub normal: Vec3,
     pub mat: Option<&'a Materials>,
     pub t: f32,
+    /// Surface coordinates of the hit, in `[0, 1]` for bounded primitives.
+    pub u: f32,
+    pub v: f32,
     pub front_face: bool,
+    /// Number of the scene object hit, counting from 1 in the order objects
+    /// were added; 0 until `HittableList` fills it in.
+    pub object: u32,
 }
 
+impl<'a> HitRecord<'a> {
+    /// Builds the record for a hit at `t`, flipping `outward_normal` so the
+    /// stored normal always points against the incoming ray.
+    pub fn new(
+        r: Ray,
+        t: f32,
+        outward_normal: Vec3,
+        (u, v): (f32, f32),
+        mat: &'a Materials,
+    ) -> HitRecord<'a> {
+        let front_face = Vec3::dot(r.direction, outward_normal) < 0.0;
+        let normal = if front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
+        HitRecord {
+            p: r.at(t),
+            normal,
+            mat: Some(mat),
+            t,
+            u,
+            v,
+            front_face,
+            object: 0,
+        }
+    }
+}
 
 pub trait Hittable {
-    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>>;
+    /// Nearest hit in `[t_min, t_max]`. Media draw the distance rays travel
+    /// through them from `sampler`; surfaces ignore it.
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32, sampler: &mut Samplers) -> Option<HitRecord<'_>>;
+    fn bounding_box(&self) -> Aabb;
+
+    /// Solid angle density of `random` picking `direction` from `origin`.
+    fn pdf_value(&self, _origin: Vec3, _direction: Vec3) -> f32 {
+        0.0
+    }
+
+    /// Direction from `origin` towards a random point on the surface.
+    fn random(&self, _origin: Vec3, _sampler: &mut Samplers) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
 }
 
 pub enum HittableEnum {
     Sphere(Sphere),
+    Plane(Plane),
+    Quad(Quad),
+    Cuboid(Cuboid),
+    Triangle(Triangle),
+    Mesh(Mesh),
+    Animated(Animated),
+    Instance(Instance),
+    ConstantMedium(ConstantMedium),
+    HeterogeneousMedium(HeterogeneousMedium),
     // HittableList(HittableList),
 }
 
 impl Hittable for HittableEnum {
-    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32, sampler: &mut Samplers) -> Option<HitRecord<'_>> {
+        match self {
+            HittableEnum::Sphere(s) => s.hit(r, t_min, t_max, sampler),
+            HittableEnum::Plane(p) => p.hit(r, t_min, t_max, sampler),
+            HittableEnum::Quad(q) => q.hit(r, t_min, t_max, sampler),
+            HittableEnum::Cuboid(c) => c.hit(r, t_min, t_max, sampler),
+            HittableEnum::Triangle(t) => t.hit(r, t_min, t_max, sampler),
+            HittableEnum::Mesh(m) => m.hit(r, t_min, t_max, sampler),
+            HittableEnum::Animated(a) => a.hit(r, t_min, t_max, sampler),
+            HittableEnum::Instance(i) => i.hit(r, t_min, t_max, sampler),
+            HittableEnum::ConstantMedium(m) => m.hit(r, t_min, t_max, sampler),
+            HittableEnum::HeterogeneousMedium(m) => m.hit(r, t_min, t_max, sampler),
+            // HittableEnum::HittableList(hl) => hl.hit(r, t_min, t_max, sampler),
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        match self {
+            HittableEnum::Sphere(s) => s.bounding_box(),
+            HittableEnum::Plane(p) => p.bounding_box(),
+            HittableEnum::Quad(q) => q.bounding_box(),
+            HittableEnum::Cuboid(c) => c.bounding_box(),
+            HittableEnum::Triangle(t) => t.bounding_box(),
+            HittableEnum::Mesh(m) => m.bounding_box(),
+            HittableEnum::Animated(a) => a.bounding_box(),
+            HittableEnum::Instance(i) => i.bounding_box(),
+            HittableEnum::ConstantMedium(m) => m.bounding_box(),
+            HittableEnum::HeterogeneousMedium(m) => m.bounding_box(),
+        }
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f32 {
         match self {
-            HittableEnum::Sphere(s) => s.hit(r, t_min, t_max),
-            // HittableEnum::HittableList(hl) => hl.hit(r, t_min, t_max),
+            HittableEnum::Sphere(s) => s.pdf_value(origin, direction),
+            HittableEnum::Plane(p) => p.pdf_value(origin, direction),
+            HittableEnum::Quad(q) => q.pdf_value(origin, direction),
+            HittableEnum::Cuboid(c) => c.pdf_value(origin, direction),
+            HittableEnum::Triangle(t) => t.pdf_value(origin, direction),
+            HittableEnum::Mesh(m) => m.pdf_value(origin, direction),
+            HittableEnum::Animated(a) => a.pdf_value(origin, direction),
+            HittableEnum::Instance(i) => i.pdf_value(origin, direction),
+            HittableEnum::ConstantMedium(m) => m.pdf_value(origin, direction),
+            HittableEnum::HeterogeneousMedium(m) => m.pdf_value(origin, direction),
+        }
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut Samplers) -> Vec3 {
+        match self {
+            HittableEnum::Sphere(s) => s.random(origin, sampler),
+            HittableEnum::Plane(p) => p.random(origin, sampler),
+            HittableEnum::Quad(q) => q.random(origin, sampler),
+            HittableEnum::Cuboid(c) => c.random(origin, sampler),
+            HittableEnum::Triangle(t) => t.random(origin, sampler),
+            HittableEnum::Mesh(m) => m.random(origin, sampler),
+            HittableEnum::Animated(a) => a.random(origin, sampler),
+            HittableEnum::Instance(i) => i.random(origin, sampler),
+            HittableEnum::ConstantMedium(m) => m.random(origin, sampler),
+            HittableEnum::HeterogeneousMedium(m) => m.random(origin, sampler),
         }
     }
 }
 
+impl HittableEnum {
+    /// Whether the object emits light and can be sampled directly. Only
+    /// primitives that implement `pdf_value` and `random` qualify.
+    fn is_light(&self) -> bool {
+        let mat = match self {
+            HittableEnum::Sphere(s) => s.material(),
+            HittableEnum::Quad(q) => q.material(),
+            HittableEnum::Triangle(t) => t.material(),
+            _ => return false,
+        };
+        mat.is_emissive()
+    }
+}
+
 pub struct HittableList {
     objects: Vec<HittableEnum>,
+    /// Object number of each of `objects`, as stored in hit records.
+    ids: Vec<u32>,
+    /// Objects without a finite bounding box, such as planes, which are
+    /// tested one by one next to the BVH.
+    unbounded: Vec<HittableEnum>,
+    unbounded_ids: Vec<u32>,
+    /// Indices into `objects` of the emitters used for light sampling.
+    lights: Vec<usize>,
+    /// Medium filling the space between surfaces.
+    fog: Option<ConstantMedium>,
+    bvh: Option<Bvh>,
 }
 
 impl HittableList {
     pub fn new() -> HittableList {
         HittableList {
             objects: Vec::new(),
+            ids: Vec::new(),
+            unbounded: Vec::new(),
+            unbounded_ids: Vec::new(),
+            lights: Vec::new(),
+            fog: None,
+            bvh: None,
         }
     }
 
     pub fn add_sphere(&mut self, sphere: Sphere) {
-        self.objects.push(HittableEnum::Sphere(sphere))
+        self.add(HittableEnum::Sphere(sphere))
+    }
+
+    pub fn add_plane(&mut self, plane: Plane) {
+        self.add(HittableEnum::Plane(plane))
+    }
+
+    pub fn add_quad(&mut self, quad: Quad) {
+        self.add(HittableEnum::Quad(quad))
+    }
+
+    pub fn add_box(&mut self, cuboid: Cuboid) {
+        self.add(HittableEnum::Cuboid(cuboid))
+    }
+
+    // The loader adds every object through `add`, these are for worlds
+    // built in code like the `add_*` above.
+    #[allow(dead_code)]
+    pub fn add_triangle(&mut self, triangle: Triangle) {
+        self.add(HittableEnum::Triangle(triangle))
+    }
+
+    #[allow(dead_code)]
+    pub fn add_mesh(&mut self, mesh: Mesh) {
+        self.add(HittableEnum::Mesh(mesh))
+    }
+
+    pub fn add(&mut self, object: HittableEnum) {
+        let id = (self.objects.len() + self.unbounded.len()) as u32 + 1;
+        if object.bounding_box().is_finite() {
+            if object.is_light() {
+                self.lights.push(self.objects.len());
+            }
+            self.objects.push(object);
+            self.ids.push(id);
+            self.bvh = None;
+        } else {
+            self.unbounded.push(object);
+            self.unbounded_ids.push(id);
+        }
+    }
+
+    pub fn set_fog(&mut self, fog: ConstantMedium) {
+        self.fog = Some(fog);
+    }
+
+    /// Builds the acceleration structure used by `hit`. Adding objects
+    /// afterwards drops it until this is called again.
+    pub fn build_bvh(&mut self) {
+        let bounds: Vec<Aabb> = self.objects.iter().map(|o| o.bounding_box()).collect();
+        self.bvh = Some(Bvh::new(&bounds));
+    }
+
+    pub fn has_lights(&self) -> bool {
+        !self.lights.is_empty()
+    }
+
+    /// Density of `sample_light` picking `direction` from `origin`, as a
+    /// mixture over all lights.
+    pub fn light_pdf(&self, origin: Vec3, direction: Vec3) -> f32 {
+        if self.lights.is_empty() {
+            return 0.0;
+        }
+        let sum: f32 = self
+            .lights
+            .iter()
+            .map(|&i| self.objects[i].pdf_value(origin, direction))
+            .sum();
+        sum / self.lights.len() as f32
+    }
+
+    /// Direction from `origin` towards a point on a uniformly chosen light.
+    pub fn sample_light(&self, origin: Vec3, sampler: &mut Samplers) -> Vec3 {
+        let pick = (sampler.next_1d() * self.lights.len() as f32) as usize;
+        let light = self.lights[pick.min(self.lights.len() - 1)];
+        self.objects[light].random(origin, sampler)
     }
 }
+
 impl Hittable for HittableList {
-    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
-        let mut hit_anything: Option<HitRecord> = None;
-        let mut closest_so_far = t_max;
-        for object in self.objects.iter() {
-            if let Some(hit) = object.hit(r, t_min, closest_so_far) {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32, sampler: &mut Samplers) -> Option<HitRecord<'_>> {
+        let (mut hit_anything, linear) = match &self.bvh {
+            Some(bvh) => {
+                let hit = bvh.traverse(r, t_min, t_max, |i, r, t_min, t_max| {
+                    let hit = self.objects[i].hit(r, t_min, t_max, sampler)?;
+                    Some(HitRecord {
+                        object: self.ids[i],
+                        ..hit
+                    })
+                });
+                (hit, 0)
+            }
+            None => (None, self.objects.len()),
+        };
+        let mut closest_so_far = hit_anything.as_ref().map_or(t_max, |hit| hit.t);
+        let objects = self.objects[..linear].iter().zip(&self.ids);
+        for (object, &id) in objects.chain(self.unbounded.iter().zip(&self.unbounded_ids)) {
+            if let Some(hit) = object.hit(r, t_min, closest_so_far, sampler) {
                 closest_so_far = hit.t;
-                hit_anything = Some(hit)
+                hit_anything = Some(HitRecord { object: id, ..hit })
             }
         }
+        // Fog ends at the nearest surface, so rays that escape reach the
+        // background unattenuated
+        if let Some(fog) = &self.fog
+            && closest_so_far.is_finite()
+            && let Some(hit) = fog.hit(r, t_min, closest_so_far, sampler)
+        {
+            hit_anything = Some(hit)
+        }
         hit_anything
     }
+
+    fn bounding_box(&self) -> Aabb {
+        let bounded = match &self.bvh {
+            Some(bvh) => bvh.bounds(),
+            None => self
+                .objects
+                .iter()
+                .fold(Aabb::EMPTY, |acc, o| acc.union(o.bounding_box())),
+        };
+        self.unbounded
+            .iter()
+            .fold(bounded, |acc, o| acc.union(o.bounding_box()))
+    }
 }
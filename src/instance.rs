@@ -0,0 +1,140 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, HittableEnum};
+use crate::mat4::Mat4;
+use crate::ray::Ray;
+use crate::sampler::Samplers;
+use crate::vec3::Vec3;
+
+use anyhow::anyhow;
+use std::sync::Arc;
+
+/// Shared geometry placed in the scene by an affine transform. Any number
+/// of instances can point at the same geometry, which is stored once.
+pub struct Instance {
+    geometry: Arc<HittableEnum>,
+    /// Object to world space.
+    transform: Mat4,
+    inverse: Mat4,
+    bounds: Aabb,
+}
+
+impl Instance {
+    pub fn new(geometry: Arc<HittableEnum>, transform: Mat4) -> anyhow::Result<Instance> {
+        let inverse = transform
+            .inverse()
+            .ok_or_else(|| anyhow!("instance transform is not invertible"))?;
+        let object_bounds = geometry.bounding_box();
+        let bounds = if object_bounds.is_finite() {
+            object_bounds
+                .corners()
+                .into_iter()
+                .fold(Aabb::EMPTY, |acc, p| acc.grow(transform.transform_point(p)))
+        } else {
+            // Infinite extents don't survive rotation, so unbounded
+            // geometry stays unbounded everywhere
+            Aabb::new(
+                Vec3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+                Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            )
+        };
+        Ok(Instance {
+            geometry,
+            transform,
+            inverse,
+            bounds,
+        })
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32, sampler: &mut Samplers) -> Option<HitRecord<'_>> {
+        // The direction is not renormalized, so `t` is the same in both
+        // spaces
+        let object_ray = Ray::new(
+            self.inverse.transform_point(r.origin),
+            self.inverse.transform_vector(r.direction),
+            r.time,
+        );
+        let mut rec = self.geometry.hit(object_ray, t_min, t_max, sampler)?;
+        rec.p = self.transform.transform_point(rec.p);
+        rec.normal = self.inverse.transform_transposed(rec.normal).unit();
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Materials;
+    use crate::sampler::SamplerKind;
+    use crate::sphere::Sphere;
+    use crate::texture::Textures;
+    use crate::vec3::assert_near;
+
+    fn sphere(center: Vec3, radius: f32) -> HittableEnum {
+        let material = Materials::Lambertian {
+            albedo: Textures::Solid(Vec3::new(0.5, 0.5, 0.5)),
+        };
+        HittableEnum::Sphere(Sphere::new(center, radius, material))
+    }
+
+    fn sampler() -> Samplers {
+        Samplers::new(SamplerKind::Independent, 0, (0, 0), 0, 1)
+    }
+
+    #[test]
+    fn instanced_sphere_matches_world_sphere() {
+        let transform = Mat4::translation(Vec3::new(2.0, 1.0, -3.0))
+            * Mat4::rotation(Vec3::new(0.0, 1.0, 1.0), 60.0)
+            * Mat4::scaling(Vec3::new(2.0, 2.0, 2.0));
+        let instance = Instance::new(Arc::new(sphere(Vec3::ZERO, 1.0)), transform).unwrap();
+        let world = sphere(Vec3::new(2.0, 1.0, -3.0), 2.0);
+
+        let rays = [
+            Ray::new(Vec3::new(2.0, 1.0, 10.0), Vec3::new(0.0, 0.0, -1.0), 0.0),
+            Ray::new(Vec3::new(-5.0, 2.0, 0.0), Vec3::new(1.0, 0.1, -0.4), 0.0),
+        ];
+        for r in rays {
+            let expected = world.hit(r, 0.001, f32::INFINITY, &mut sampler()).unwrap();
+            let got = instance
+                .hit(r, 0.001, f32::INFINITY, &mut sampler())
+                .unwrap();
+            assert!((got.t - expected.t).abs() < 1e-4);
+            assert_near(got.p, expected.p);
+            assert_near(got.normal, expected.normal);
+        }
+        let miss = Ray::new(Vec3::new(10.0, 10.0, 10.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        assert!(
+            instance
+                .hit(miss, 0.001, f32::INFINITY, &mut sampler())
+                .is_none()
+        );
+
+        // The box of the rotated unit cube's corners contains the sphere's
+        let bounds = instance.bounding_box();
+        let tight = world.bounding_box();
+        for axis in 0..3 {
+            assert!(bounds.min[axis] <= tight.min[axis] + 1e-4);
+            assert!(bounds.max[axis] >= tight.max[axis] - 1e-4);
+        }
+    }
+
+    #[test]
+    fn stretched_sphere_normals_stay_perpendicular() {
+        // An ellipsoid with semi-axes 3, 1 and 1, whose normal at (x, y, z)
+        // points along (x / 9, y, z)
+        let transform = Mat4::scaling(Vec3::new(3.0, 1.0, 1.0));
+        let instance = Instance::new(Arc::new(sphere(Vec3::ZERO, 1.0)), transform).unwrap();
+        let r = Ray::new(Vec3::new(1.5, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let rec = instance
+            .hit(r, 0.001, f32::INFINITY, &mut sampler())
+            .unwrap();
+        let p = rec.p;
+        assert!((p.x() * p.x() / 9.0 + p.y() * p.y() + p.z() * p.z() - 1.0).abs() < 1e-4);
+        assert_near(rec.normal, Vec3::new(p.x() / 9.0, p.y(), p.z()).unit());
+    }
+}
@@ -0,0 +1,156 @@
+use crate::render::Renderer;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Flags a render checks between pieces of work to learn whether it should
+/// stop or wait.
+#[derive(Default)]
+pub struct Control {
+    cancelled: AtomicBool,
+    paused: AtomicBool,
+    /// Held while changing either flag, so a thread about to wait can't
+    /// miss the wake-up.
+    clock: Mutex<PauseClock>,
+    resumed: Condvar,
+}
+
+/// Time a render has spent paused.
+#[derive(Default)]
+struct PauseClock {
+    since: Option<Instant>,
+    total: Duration,
+}
+
+impl Control {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Blocks while the render is paused, then returns whether it should
+    /// carry on.
+    pub fn proceed(&self) -> bool {
+        if self.paused.load(Ordering::Relaxed) {
+            let mut clock = self.clock.lock().unwrap();
+            while self.paused.load(Ordering::Relaxed) && !self.is_cancelled() {
+                clock = self.resumed.wait(clock).unwrap();
+            }
+        }
+        !self.is_cancelled()
+    }
+
+    /// Time spent paused so far, which time limits shouldn't count.
+    pub fn paused_time(&self) -> Duration {
+        let clock = self.clock.lock().unwrap();
+        clock.total + clock.since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    fn set_paused(&self, pause: bool) {
+        let mut clock = self.clock.lock().unwrap();
+        match (pause, clock.since) {
+            (true, None) => clock.since = Some(Instant::now()),
+            (false, Some(since)) => {
+                clock.total += since.elapsed();
+                clock.since = None;
+            }
+            _ => {}
+        }
+        self.paused.store(pause, Ordering::Relaxed);
+        self.resumed.notify_all();
+    }
+
+    fn cancel(&self) {
+        let _clock = self.clock.lock().unwrap();
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.resumed.notify_all();
+    }
+}
+
+/// Render running on a background thread, which can be paused, resumed or
+/// cancelled, and then joined to get the renderer back, for example to move
+/// its camera and start over.
+pub struct RenderJob {
+    handle: JoinHandle<Renderer>,
+    control: Arc<Control>,
+}
+
+impl RenderJob {
+    /// Runs `render` on a new thread. It should call [`Control::proceed`]
+    /// between pieces of work and return early once it says no.
+    pub fn spawn<F>(renderer: Renderer, render: F) -> RenderJob
+    where
+        F: FnOnce(&Renderer, &Control) + Send + 'static,
+    {
+        let control = Arc::new(Control::default());
+        let shared = Arc::clone(&control);
+        let handle = thread::spawn(move || {
+            render(&renderer, &shared);
+            renderer
+        });
+        RenderJob { handle, control }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.control.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.control.is_cancelled()
+    }
+
+    pub fn paused_time(&self) -> Duration {
+        self.control.paused_time()
+    }
+
+    pub fn pause(&self) {
+        self.control.set_paused(true);
+    }
+
+    pub fn resume(&self) {
+        self.control.set_paused(false);
+    }
+
+    /// Asks the render to stop; work already underway still completes.
+    pub fn cancel(&self) {
+        self.control.cancel();
+    }
+
+    /// Waits for the render to stop, handing back the renderer.
+    pub fn join(self) -> Renderer {
+        self.handle.join().expect("render thread panicked")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paused_time_counts_only_pauses() {
+        let control = Control::default();
+        thread::sleep(Duration::from_millis(20));
+        assert!(control.paused_time().is_zero());
+        control.set_paused(true);
+        thread::sleep(Duration::from_millis(30));
+        control.set_paused(false);
+        let paused = control.paused_time();
+        assert!(paused >= Duration::from_millis(30));
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(control.paused_time(), paused);
+    }
+
+    #[test]
+    fn cancel_wakes_paused_workers() {
+        let control = Arc::new(Control::default());
+        control.set_paused(true);
+        let worker = {
+            let control = Arc::clone(&control);
+            thread::spawn(move || control.proceed())
+        };
+        thread::sleep(Duration::from_millis(20));
+        control.cancel();
+        assert!(!worker.join().unwrap());
+    }
+}
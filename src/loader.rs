@@ -0,0 +1,626 @@
+//! Loader for the text scene format.
+//!
+//! A scene file is a list of directives, one per line. Tokens are separated
+//! by whitespace and `#` starts a comment that runs to the end of the line.
+//!
+//! ```text
+//! image width 800 aspect 1.5 samples 64 depth 10
+//! image threshold 0.02 minsamples 16     # adaptive sampling, samples is the
+//!                                        # most any pixel gets
+//! image sampler sobol                    # independent, stratified, halton,
+//!                                        # sobol, blue-noise
+//! image exposure 1 tonemap aces          # stops; or clamp, reinhard, agx
+//! image tonemap extended-reinhard white 8   # radiance shown as white
+//! camera lookfrom 13 2 3 lookat 0 0 0 vup 0 1 0 vfov 20 aperture 0.1 focus 10
+//! camera shutter 0 1                     # open and close time
+//!
+//! texture white solid 0.9 0.9 0.9
+//! texture tiles checker 0.5 white dark   # cell size, even and odd textures
+//! texture wood image wood.jpg repeat     # or clamp, mirror
+//! texture stone noise 4 marble           # scale, perlin|turbulence|marble
+//!
+//! material ground lambertian 0.5 0.5 0.5
+//! material floor lambertian texture tiles
+//! material steel metal 0.7 0.6 0.5 0.1   # albedo, fuzz
+//! material glass dielectric 1.5          # index of refraction
+//! material lamp light 4 4 4              # emitted radiance
+//! material smoke isotropic 0.8 0.8 0.8   # phase functions for media
+//! material haze henyey-greenstein 0.9 0.9 0.9 0.6   # albedo, asymmetry g
+//!
+//! sphere 0 -1000 0 1000 ground           # center, radius, material
+//! sphere 0 1 0 1 glass
+//! sphere 0 4 0 0.5 lamp
+//!
+//! plane 0 0 0 0 1 0 ground              # point, normal
+//! quad -1 3 -1 2 0 0 0 0 2 lamp          # corner, edge u, edge v
+//! box 2 0 -1 3 1 0 steel                 # opposite corners
+//! box 2 0 -1 3 1 0 steel rotate 0 15 0   # rotated about its center, degrees
+//! triangle 0 0 2 1 0 2 0 1 2 steel       # vertices a, b, c
+//! mesh models/teapot.obj                 # Wavefront OBJ, materials from MTL
+//! mesh models/teapot.obj steel           # same with a single material
+//!
+//! sphere 2 1 0 0.5 steel move 0 0.5 0    # moves by the offset while the
+//!                                        # shutter goes from time 0 to 1
+//! box 0 0 0 1 1 1 steel spin 0 1 0 90    # rotates about its center
+//! sphere 0 1 3 0.5 glass keyframe 0 0 0 0 keyframe 0.5 0 1 0 keyframe 1 1 1 0
+//!
+//! geometry tree mesh models/tree.obj    # shared, only drawn by instances
+//! instance tree translate 2 0 1 rotate 0 45 0 scale 2 2 2
+//!
+//! medium smoke 0.5 box 0 0 0 1 1 1 glass          # density, boundary
+//! medium smoke grid cloud.grid 4 sphere 0 1 0 1 glass   # grid, scale
+//! medium smoke noise 2 3 sphere 0 1 0 1 glass         # frequency, scale
+//! fog 0.05 haze                          # density between all surfaces
+//!
+//! background 0 0 0                       # or `background sky`
+//! background environment studio.hdr rotate 90 intensity 1.5
+//! ```
+//!
+//! `triangle` optionally takes `normals` followed by three per-vertex normals
+//! and `uvs` followed by three texture coordinate pairs. Mesh paths are
+//! relative to the scene file, as are image textures and environment maps.
+//! Environment maps are equirectangular Radiance HDR or OpenEXR images,
+//! rotated in degrees around the vertical axis.
+//!
+//! Instances apply their transforms in the order given; rotations are in
+//! degrees about x, then y, then z. Every instance shares the geometry's
+//! memory, whatever object directive declared it.
+//!
+//! A medium fills a convex boundary, given as any object directive whose
+//! material is ignored. Density grids are text files with the grid size
+//! followed by one value per voxel, x varying fastest; the grid stretches
+//! over the boundary's bounding box.
+//!
+//! `image` and `camera` take keyword/value pairs in any order; keywords that
+//! are left out keep their defaults. Textures and materials must be declared
+//! before they are used. Lambertian and metal albedos are either a color or
+//! `texture` followed by a texture name.
+
+use crate::aabb::Aabb;
+use crate::animated::{Animated, Keyframes};
+use crate::background::Background;
+use crate::camera::CameraSettings;
+use crate::cuboid::Cuboid;
+use crate::environment::EnvironmentMap;
+use crate::hittable::{Hittable, HittableEnum, HittableList};
+use crate::instance::Instance;
+use crate::mat4::Mat4;
+use crate::material::Materials;
+use crate::medium::{ConstantMedium, Density, DensityGrid, HeterogeneousMedium};
+use crate::mesh::Mesh;
+use crate::perlin::Perlin;
+use crate::plane::Plane;
+use crate::quad::Quad;
+use crate::sampler::{SamplerKind, splitmix64};
+use crate::scene::{ImageSettings, Scene};
+use crate::sphere::Sphere;
+use crate::texture::{ImageTexture, NoiseKind, Textures, WrapMode};
+use crate::tonemap::ToneMapper;
+use crate::triangle::Triangle;
+use crate::vec3::Vec3;
+
+use anyhow::{Context, anyhow, bail};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::{FromStr, SplitWhitespace};
+use std::sync::Arc;
+
+/// Loads the scene at `path`; `seed` drives its procedural noise.
+pub fn load_scene(path: &Path, seed: u64) -> anyhow::Result<Scene> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read scene {}", path.display()))?;
+    let base_dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    parse_scene(&source, base_dir, seed).map_err(|e| anyhow!("{}:{:#}", path.display(), e))
+}
+
+/// Parses a whole scene. Errors are prefixed with the offending line number.
+fn parse_scene(source: &str, base_dir: PathBuf, seed: u64) -> anyhow::Result<Scene> {
+    let mut loader = Loader {
+        base_dir,
+        seed,
+        perlin_count: Cell::new(0),
+        world: HittableList::new(),
+        camera: CameraSettings::default(),
+        image: ImageSettings::default(),
+        background: Background::Sky,
+        textures: HashMap::new(),
+        materials: HashMap::new(),
+        geometry: HashMap::new(),
+    };
+    for (index, line) in source.lines().enumerate() {
+        let content = line.split('#').next().unwrap_or_default();
+        let mut tokens = Tokens {
+            iter: content.split_whitespace(),
+        };
+        if let Some(keyword) = tokens.iter.next() {
+            loader
+                .directive(keyword, &mut tokens)
+                .and_then(|()| tokens.finish())
+                .map_err(|e| anyhow!("{}: {:#}", index + 1, e))?;
+        }
+    }
+    loader.world.build_bvh();
+    Ok(Scene {
+        world: loader.world,
+        camera: loader.camera,
+        image: loader.image,
+        background: loader.background,
+    })
+}
+
+struct Loader {
+    base_dir: PathBuf,
+    seed: u64,
+    /// Noise fields created so far, so each gets its own.
+    perlin_count: Cell<u64>,
+    world: HittableList,
+    camera: CameraSettings,
+    image: ImageSettings,
+    background: Background,
+    textures: HashMap<String, Textures>,
+    materials: HashMap<String, Materials>,
+    geometry: HashMap<String, Arc<HittableEnum>>,
+}
+
+impl Loader {
+    fn directive(&mut self, keyword: &str, tokens: &mut Tokens) -> anyhow::Result<()> {
+        match keyword {
+            "image" => self.image_settings(tokens),
+            "camera" => self.camera_settings(tokens),
+            "background" => {
+                self.background = if tokens.accept("sky") {
+                    Background::Sky
+                } else if tokens.accept("environment") {
+                    self.environment(tokens)?
+                } else {
+                    Background::Solid(tokens.vec3("background color")?)
+                };
+                Ok(())
+            }
+            "material" => {
+                let name = tokens.word("material name")?;
+                let material = self.parse_material(tokens)?;
+                self.materials.insert(name.to_string(), material);
+                Ok(())
+            }
+            "texture" => {
+                let name = tokens.word("texture name")?;
+                let texture = self.parse_texture(tokens)?;
+                self.textures.insert(name.to_string(), texture);
+                Ok(())
+            }
+            "sphere" | "plane" | "quad" | "box" | "triangle" | "mesh" => {
+                let object = self.object(keyword, tokens)?;
+                self.world.add(motion(object, tokens)?);
+                Ok(())
+            }
+            "geometry" => {
+                let name = tokens.word("geometry name")?;
+                let keyword = tokens.word("object")?;
+                let object = self.object(keyword, tokens)?;
+                self.geometry.insert(name.to_string(), Arc::new(object));
+                Ok(())
+            }
+            "instance" => {
+                let object = HittableEnum::Instance(self.instance(tokens)?);
+                let object = motion(object, tokens)?;
+                self.world.add(object);
+                Ok(())
+            }
+            "medium" => {
+                let object = self.medium(tokens)?;
+                let object = motion(object, tokens)?;
+                self.world.add(object);
+                Ok(())
+            }
+            "fog" => {
+                let density = tokens.number("fog density")?;
+                let phase = self.material(tokens)?;
+                self.world
+                    .set_fog(ConstantMedium::unbounded(density, phase));
+                Ok(())
+            }
+            _ => bail!("unknown directive '{}'", keyword),
+        }
+    }
+
+    fn object(&self, keyword: &str, tokens: &mut Tokens) -> anyhow::Result<HittableEnum> {
+        match keyword {
+            "sphere" => {
+                let center = tokens.vec3("sphere center")?;
+                let radius = tokens.number("sphere radius")?;
+                let material = self.material(tokens)?;
+                Ok(HittableEnum::Sphere(Sphere::new(center, radius, material)))
+            }
+            "plane" => {
+                let point = tokens.vec3("plane point")?;
+                let normal = tokens.vec3("plane normal")?;
+                let material = self.material(tokens)?;
+                Ok(HittableEnum::Plane(Plane::new(point, normal, material)))
+            }
+            "quad" => {
+                let q = tokens.vec3("quad corner")?;
+                let u = tokens.vec3("quad edge")?;
+                let v = tokens.vec3("quad edge")?;
+                let material = self.material(tokens)?;
+                Ok(HittableEnum::Quad(Quad::new(q, u, v, material)))
+            }
+            "box" => {
+                let a = tokens.vec3("box corner")?;
+                let b = tokens.vec3("box corner")?;
+                let bounds = Aabb::new(a, b);
+                let extent = bounds.extent();
+                if extent.x() == 0.0 || extent.y() == 0.0 || extent.z() == 0.0 {
+                    bail!("box corners must differ along every axis");
+                }
+                let material = self.material(tokens)?;
+                let cuboid = if tokens.accept("rotate") {
+                    let rotation = tokens.vec3("box rotation")?;
+                    Cuboid::oriented(bounds.centroid(), extent, rotation, material)
+                } else {
+                    Cuboid::new(a, b, material)
+                };
+                Ok(HittableEnum::Cuboid(cuboid))
+            }
+            "triangle" => {
+                let a = tokens.vec3("triangle vertex")?;
+                let b = tokens.vec3("triangle vertex")?;
+                let c = tokens.vec3("triangle vertex")?;
+                let mut triangle = Triangle::new(a, b, c, self.material(tokens)?);
+                if tokens.accept("normals") {
+                    triangle = triangle.with_normals([
+                        tokens.vec3("vertex normal")?,
+                        tokens.vec3("vertex normal")?,
+                        tokens.vec3("vertex normal")?,
+                    ]);
+                }
+                if tokens.accept("uvs") {
+                    let mut uv = || -> anyhow::Result<(f32, f32)> {
+                        Ok((
+                            tokens.number("texture coordinate")?,
+                            tokens.number("texture coordinate")?,
+                        ))
+                    };
+                    triangle = triangle.with_uvs([uv()?, uv()?, uv()?]);
+                }
+                Ok(HittableEnum::Triangle(triangle))
+            }
+            "mesh" => {
+                let path = self.base_dir.join(tokens.word("mesh path")?);
+                let material = match tokens.peek() {
+                    Some(word) if !MOTION_KEYWORDS.contains(&word) => Some(self.material(tokens)?),
+                    _ => None,
+                };
+                Ok(HittableEnum::Mesh(Mesh::load_obj(&path, material)?))
+            }
+            _ => bail!("unknown object '{}'", keyword),
+        }
+    }
+
+    fn instance(&self, tokens: &mut Tokens) -> anyhow::Result<Instance> {
+        let name = tokens.word("geometry name")?;
+        let geometry = self
+            .geometry
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown geometry '{}'", name))?;
+        let mut transform = Mat4::IDENTITY;
+        loop {
+            let step = if tokens.accept("translate") {
+                Mat4::translation(tokens.vec3("translation")?)
+            } else if tokens.accept("rotate") {
+                let degrees = tokens.vec3("rotation")?;
+                Mat4::rotation(Vec3::new(0.0, 0.0, 1.0), degrees.z())
+                    * Mat4::rotation(Vec3::new(0.0, 1.0, 0.0), degrees.y())
+                    * Mat4::rotation(Vec3::new(1.0, 0.0, 0.0), degrees.x())
+            } else if tokens.accept("scale") {
+                Mat4::scaling(tokens.vec3("scale")?)
+            } else {
+                break;
+            };
+            transform = step * transform;
+        }
+        Instance::new(geometry, transform)
+    }
+
+    fn medium(&self, tokens: &mut Tokens) -> anyhow::Result<HittableEnum> {
+        let phase = self.material(tokens)?;
+        let density = if tokens.accept("grid") {
+            let path = self.base_dir.join(tokens.word("grid path")?);
+            Density::Grid {
+                grid: Arc::new(DensityGrid::load(&path)?),
+                scale: tokens.number("grid scale")?,
+            }
+        } else if tokens.accept("noise") {
+            Density::Noise {
+                perlin: Arc::new(self.perlin()),
+                frequency: tokens.number("noise frequency")?,
+                scale: tokens.number("noise scale")?,
+            }
+        } else {
+            let density = tokens.number("medium density")?;
+            let boundary = self.object(tokens.word("medium boundary")?, tokens)?;
+            let medium = ConstantMedium::new(boundary, density, phase);
+            return Ok(HittableEnum::ConstantMedium(medium));
+        };
+        let boundary = self.object(tokens.word("medium boundary")?, tokens)?;
+        let medium = HeterogeneousMedium::new(boundary, density, phase);
+        Ok(HittableEnum::HeterogeneousMedium(medium))
+    }
+
+    fn image_settings(&mut self, tokens: &mut Tokens) -> anyhow::Result<()> {
+        while let Some(key) = tokens.iter.next() {
+            match key {
+                "width" => self.image.width = tokens.number("image width")?,
+                "aspect" => self.image.aspect_ratio = tokens.number("aspect ratio")?,
+                "samples" => self.image.samples_per_pixel = tokens.number("sample count")?,
+                "depth" => self.image.max_depth = tokens.number("max depth")?,
+                "threshold" => self.image.adaptive_threshold = tokens.number("error threshold")?,
+                "minsamples" => self.image.min_samples = tokens.number("minimum sample count")?,
+                "sampler" => {
+                    let name = tokens.word("sampler")?;
+                    self.image.sampler = SamplerKind::from_name(name)
+                        .ok_or_else(|| anyhow!("unknown sampler '{}'", name))?;
+                }
+                "exposure" => self.image.display.exposure = tokens.number("exposure")?,
+                "tonemap" => {
+                    let name = tokens.word("tone mapper")?;
+                    self.image.display.tone_mapper = ToneMapper::from_name(name)
+                        .ok_or_else(|| anyhow!("unknown tone mapper '{}'", name))?;
+                }
+                "white" => self.image.display.white = tokens.number("white point")?,
+                _ => bail!("unknown image setting '{}'", key),
+            }
+        }
+        // Pixel coordinates are mapped to the viewport as x / (width - 1)
+        if self.image.width < 2 {
+            bail!("image width must be at least 2 pixels");
+        }
+        let aspect_ratio = self.image.aspect_ratio;
+        if !(aspect_ratio > 0.0 && aspect_ratio.is_finite()) {
+            bail!("aspect ratio must be a positive number");
+        }
+        if self.image.samples_per_pixel == 0 {
+            bail!("sample count must be positive");
+        }
+        let threshold = self.image.adaptive_threshold;
+        if !threshold.is_finite() || threshold < 0.0 {
+            bail!("error threshold must not be negative");
+        }
+        let white = self.image.display.white;
+        if !white.is_finite() || white <= 0.0 {
+            bail!("white point must be positive");
+        }
+        Ok(())
+    }
+
+    fn camera_settings(&mut self, tokens: &mut Tokens) -> anyhow::Result<()> {
+        while let Some(key) = tokens.iter.next() {
+            match key {
+                "lookfrom" => self.camera.lookfrom = tokens.vec3("lookfrom")?,
+                "lookat" => self.camera.lookat = tokens.vec3("lookat")?,
+                "vup" => self.camera.vup = tokens.vec3("vup")?,
+                "vfov" => self.camera.vfov = tokens.number("vfov")?,
+                "aperture" => self.camera.aperture = tokens.number("aperture")?,
+                "focus" => self.camera.focus_dist = tokens.number("focus distance")?,
+                "shutter" => {
+                    self.camera.shutter_open = tokens.number("shutter open time")?;
+                    self.camera.shutter_close = tokens.number("shutter close time")?;
+                }
+                _ => bail!("unknown camera setting '{}'", key),
+            }
+        }
+        Ok(())
+    }
+
+    fn environment(&self, tokens: &mut Tokens) -> anyhow::Result<Background> {
+        let path = self.base_dir.join(tokens.word("environment path")?);
+        let mut rotation = 0.0;
+        let mut intensity = 1.0;
+        while let Some(key) = tokens.iter.next() {
+            match key {
+                "rotate" => rotation = tokens.number("environment rotation")?,
+                "intensity" => intensity = tokens.number("environment intensity")?,
+                _ => bail!("unknown environment setting '{}'", key),
+            }
+        }
+        let map = EnvironmentMap::load(&path, rotation, intensity)?;
+        Ok(Background::Environment(Arc::new(map)))
+    }
+
+    /// New noise field, seeded by the scene seed and creation order so
+    /// every one differs but renders stay reproducible.
+    fn perlin(&self) -> Perlin {
+        let count = self.perlin_count.get();
+        self.perlin_count.set(count + 1);
+        Perlin::new(splitmix64(self.seed.wrapping_add(count)))
+    }
+
+    fn material(&self, tokens: &mut Tokens) -> anyhow::Result<Materials> {
+        let name = tokens.word("material name")?;
+        self.materials
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown material '{}'", name))
+    }
+
+    fn texture(&self, tokens: &mut Tokens) -> anyhow::Result<Textures> {
+        let name = tokens.word("texture name")?;
+        self.textures
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("unknown texture '{}'", name))
+    }
+
+    /// Either `texture <name>` or a constant color.
+    fn albedo(&self, tokens: &mut Tokens) -> anyhow::Result<Textures> {
+        if tokens.accept("texture") {
+            self.texture(tokens)
+        } else {
+            Ok(Textures::Solid(tokens.vec3("albedo")?))
+        }
+    }
+
+    fn parse_material(&self, tokens: &mut Tokens) -> anyhow::Result<Materials> {
+        let kind = tokens.word("material type")?;
+        match kind {
+            "lambertian" => Ok(Materials::Lambertian {
+                albedo: self.albedo(tokens)?,
+            }),
+            "metal" => Ok(Materials::Metal {
+                albedo: self.albedo(tokens)?,
+                fuzz: tokens.number("fuzz")?,
+            }),
+            "dielectric" => Ok(Materials::Dielectric {
+                ir: tokens.number("index of refraction")?,
+            }),
+            "light" => Ok(Materials::DiffuseLight {
+                emit: tokens.vec3("emitted radiance")?,
+            }),
+            "isotropic" => Ok(Materials::Isotropic {
+                albedo: self.albedo(tokens)?,
+            }),
+            "henyey-greenstein" => Ok(Materials::HenyeyGreenstein {
+                albedo: self.albedo(tokens)?,
+                g: tokens.number("asymmetry")?,
+            }),
+            _ => bail!("unknown material type '{}'", kind),
+        }
+    }
+
+    fn parse_texture(&self, tokens: &mut Tokens) -> anyhow::Result<Textures> {
+        let kind = tokens.word("texture type")?;
+        match kind {
+            "solid" => Ok(Textures::Solid(tokens.vec3("color")?)),
+            "checker" => {
+                let size: f32 = tokens.number("checker size")?;
+                Ok(Textures::Checker {
+                    inv_scale: 1.0 / size,
+                    even: Arc::new(self.texture(tokens)?),
+                    odd: Arc::new(self.texture(tokens)?),
+                })
+            }
+            "image" => {
+                let path = self.base_dir.join(tokens.word("image path")?);
+                let wrap = match tokens.peek() {
+                    None => WrapMode::Repeat,
+                    Some(_) => match tokens.word("wrap mode")? {
+                        "repeat" => WrapMode::Repeat,
+                        "clamp" => WrapMode::Clamp,
+                        "mirror" => WrapMode::Mirror,
+                        mode => bail!("unknown wrap mode '{}'", mode),
+                    },
+                };
+                Ok(Textures::Image(Arc::new(ImageTexture::load(&path, wrap)?)))
+            }
+            "noise" => {
+                let scale = tokens.number("noise scale")?;
+                let kind = match tokens.peek() {
+                    None => NoiseKind::Perlin,
+                    Some(_) => match tokens.word("noise type")? {
+                        "perlin" => NoiseKind::Perlin,
+                        "turbulence" => NoiseKind::Turbulence,
+                        "marble" => NoiseKind::Marble,
+                        kind => bail!("unknown noise type '{}'", kind),
+                    },
+                };
+                let perlin = self.perlin();
+                Ok(Textures::Noise {
+                    perlin: Arc::new(perlin),
+                    scale,
+                    kind,
+                })
+            }
+            _ => bail!("unknown texture type '{}'", kind),
+        }
+    }
+}
+
+const MOTION_KEYWORDS: [&str; 3] = ["move", "keyframe", "spin"];
+
+/// Wraps `object` in an `Animated` if motion keywords follow it.
+fn motion(object: HittableEnum, tokens: &mut Tokens) -> anyhow::Result<HittableEnum> {
+    let mut translation = Vec::new();
+    let mut spin = None;
+    while let Some(key) = tokens.iter.next() {
+        match key {
+            "move" => {
+                translation.push((0.0, Vec3::ZERO));
+                translation.push((1.0, tokens.vec3("move offset")?));
+            }
+            "keyframe" => translation.push((
+                tokens.number("keyframe time")?,
+                tokens.vec3("keyframe offset")?,
+            )),
+            "spin" => {
+                let axis = tokens.vec3("spin axis")?;
+                let degrees: f32 = tokens.number("spin angle")?;
+                if axis.near_zero() {
+                    bail!("spin axis must not be zero");
+                }
+                spin = Some((axis, degrees));
+            }
+            _ => bail!("unexpected '{}'", key),
+        }
+    }
+    if translation.is_empty() && spin.is_none() {
+        return Ok(object);
+    }
+    if !object.bounding_box().is_finite() {
+        bail!("unbounded objects cannot move or spin");
+    }
+    let mut animated = Animated::new(object);
+    if !translation.is_empty() {
+        animated = animated.with_translation(Keyframes::new(translation));
+    }
+    if let Some((axis, degrees)) = spin {
+        animated = animated.with_rotation(axis, Keyframes::new(vec![(0.0, 0.0), (1.0, degrees)]));
+    }
+    Ok(HittableEnum::Animated(animated))
+}
+
+struct Tokens<'a> {
+    iter: SplitWhitespace<'a>,
+}
+
+impl<'a> Tokens<'a> {
+    fn word(&mut self, what: &str) -> anyhow::Result<&'a str> {
+        self.iter.next().ok_or_else(|| anyhow!("missing {}", what))
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.iter.clone().next()
+    }
+
+    /// Consumes the next token if it is `word`.
+    fn accept(&mut self, word: &str) -> bool {
+        let matches = self.peek() == Some(word);
+        if matches {
+            self.iter.next();
+        }
+        matches
+    }
+
+    fn number<T: FromStr>(&mut self, what: &str) -> anyhow::Result<T> {
+        let token = self.word(what)?;
+        token
+            .parse()
+            .map_err(|_| anyhow!("invalid {} '{}'", what, token))
+    }
+
+    fn vec3(&mut self, what: &str) -> anyhow::Result<Vec3> {
+        Ok(Vec3::new(
+            self.number(what)?,
+            self.number(what)?,
+            self.number(what)?,
+        ))
+    }
+
+    fn finish(&mut self) -> anyhow::Result<()> {
+        match self.iter.next() {
+            Some(token) => bail!("unexpected '{}'", token),
+            None => Ok(()),
+        }
+    }
+}
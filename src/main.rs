@@ -1,79 +1,127 @@
+mod aabb;
+mod adaptive;
+mod animated;
+mod aov;
+mod background;
+mod blue_noise;
+mod bvh;
 mod camera;
+mod cli;
+mod cuboid;
+mod denoise;
+mod distribution;
+mod environment;
+mod framebuffer;
+mod halton;
 mod hittable;
+mod instance;
+mod job;
+mod loader;
+mod mat4;
 mod material;
+mod medium;
+mod mesh;
+mod navigation;
+mod onb;
+mod output;
+mod perlin;
+mod plane;
+mod progressive;
+mod quad;
 mod ray;
 mod render;
+mod sampler;
 mod scene;
+mod sobol;
 mod sphere;
+mod texture;
+mod tile;
+mod tonemap;
+mod triangle;
 mod vec3;
 
-use crate::camera::Camera;
+use crate::adaptive::PixelEstimate;
+use crate::aov::Aov;
+use crate::denoise::Denoiser;
+use crate::framebuffer::Framebuffer;
+use crate::job::RenderJob;
+use crate::navigation::Navigation;
+use crate::tile::{Tile, Tiling};
+use crate::tonemap::DisplayTransform;
 use crate::vec3::Vec3;
 
-use anyhow::Context;
-use rand::prelude::*;
+use anyhow::{Context, bail};
+use clap::Parser;
 use raylib::prelude::*;
-use rayon::prelude::*;
 use std::fmt::Write;
-use std::sync::mpsc;
-use std::thread;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, mpsc};
 use std::time::{Duration, Instant};
 
 fn main() -> anyhow::Result<()> {
-    // Image
-    let aspect_ratio: f32 = 3.0 / 2.0;
-    let image_width = 1200;
-    let image_height: u32 = (image_width as f32 / aspect_ratio) as u32;
-    let samples_per_pixel = 100;
-    let max_depth = 10;
+    let args = cli::Args::parse();
+    let seed = args.seed.unwrap_or_else(rand::random);
 
     // World
-    let world = scene::random_scene();
-
-    // Camera
-    let lookfrom = Vec3::new(13.0, 2.0, 3.0);
-    let lookat = Vec3::new(0.0, 0.0, 0.0);
-    let vup = Vec3::new(0.0, 1.0, 0.0);
-    let dist_to_focus = 10.0;
-    let aperture = 0.1;
-    let camera = Camera::new(
-        lookfrom,
-        lookat,
-        vup,
-        20.0,
-        aspect_ratio,
-        aperture,
-        dist_to_focus,
-    );
+    let mut scene = match scene::builtin_scene(&args.scene, seed) {
+        Some(scene) => scene,
+        None => loader::load_scene(Path::new(&args.scene), seed)?,
+    };
+    args.apply(&mut scene.image, &mut scene.camera);
+    let aspect_ratio = scene.image.aspect_ratio;
+    if !(aspect_ratio > 0.0 && aspect_ratio.is_finite()) {
+        bail!("Aspect ratio must be a positive number");
+    }
+    let image_width = scene.image.width;
+    let image_height = scene.image.height();
+    let renderer = render::Renderer {
+        camera: scene.camera.build(aspect_ratio),
+        world: scene.world,
+        background: scene.background,
+        image_width,
+        image_height,
+        max_depth: scene.image.max_depth,
+        samples_per_pixel: scene.image.samples_per_pixel,
+        adaptive: scene.image.adaptive(),
+        sampler: scene.image.sampler,
+        seed,
+    };
+    let max_samples = renderer.samples_per_pixel;
+    let display = scene.image.display;
 
-    // Generate coordinates with better memory allocation
-    let total_pixels = (image_width * image_height) as usize;
-    let mut coords: Vec<(u32, u32)> = Vec::with_capacity(total_pixels);
-    for x in 0..image_width {
-        for y in 0..image_height {
-            coords.push((x, y));
-        }
+    let budget = args.budget(&scene.image);
+
+    // Fail on bad output paths before spending time on the render
+    for path in args.outputs.iter().chain(&args.sample_map) {
+        output::OutputFormat::from_path(path)?;
     }
-    coords.shuffle(&mut rand::rng());
+    let paths = if args.headless && args.outputs.is_empty() {
+        vec![PathBuf::from("render.png"), PathBuf::from("render.ppm")]
+    } else {
+        args.outputs.clone()
+    };
+    let outputs = Outputs {
+        paths,
+        sample_map: args.sample_map.clone(),
+        aovs: args.aovs.clone(),
+        display,
+        denoiser: args.denoiser(),
+    };
 
-    let (sender, receiver) = mpsc::channel::<(Vec3, (u32, u32))>();
+    if args.headless {
+        println!("Scene: {} (seed {})", args.scene, seed);
+        return render_headless(&renderer, &outputs, budget, args.tiling());
+    }
 
-    let render_start_time = Instant::now();
+    let total_pixels = (image_width * image_height) as usize;
+    let mut navigation = Navigation::new(scene.camera);
+    let tiling = args.tiling();
+    let (sender, mut receiver) = mpsc::channel::<Update>();
 
-    // Rendering thread
-    thread::spawn(move || {
-        coords.into_par_iter().for_each(|coords| {
-            let pixel = render::render_pixel(
-                coords,
-                (image_width, image_height),
-                &camera,
-                &world,
-                max_depth,
-                samples_per_pixel,
-            );
-            sender.send((pixel, coords)).expect("send rendered pixel");
-        });
-    });
+    let mut render_start_time = Instant::now();
+    let mut active_budget = budget;
+    let framebuffer = Arc::new(Framebuffer::new(image_width, image_height, tiling));
+    let mut job = start_render(renderer, Arc::clone(&framebuffer), active_budget, sender);
 
     let (mut rl, thread) = raylib::init()
         .width(image_width as i32)
@@ -83,12 +131,19 @@ fn main() -> anyhow::Result<()> {
         .build();
     rl.set_target_fps(60);
 
-    let mut image = Image::gen_image_color(image_width as i32, image_height as i32, Color::BLACK);
-    let mut texture = rl
+    let image = Image::gen_image_color(image_width as i32, image_height as i32, Color::BLACK);
+    let texture = rl
         .load_texture_from_image(&thread, &image)
         .context("Failed to create initial texture")?;
 
+    // The window's copy of the image, the tiles it is missing updates of,
+    // and room to pack the part of it the texture is missing
+    let mut rgba = [0, 0, 0, 255].repeat(total_pixels);
+    let mut dirty = vec![false; framebuffer.tiles().len()];
+    let mut upload = Vec::with_capacity(rgba.len());
     let mut pixels_rendered = 0;
+    let mut passes = 0;
+    let mut finished = false;
     let mut rendering_complete = false;
     let mut render_time: Option<std::time::Duration> = None;
     let mut last_texture_update = Instant::now();
@@ -97,30 +152,100 @@ fn main() -> anyhow::Result<()> {
     const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
 
     while !rl.window_should_close() {
-        for (pixel, (x, y)) in receiver.try_iter() {
-            let red = (256.0 * pixel.x().clamp(0.0, 0.999)) as u8;
-            let green = (256.0 * pixel.y().clamp(0.0, 0.999)) as u8;
-            let blue = (256.0 * pixel.z().clamp(0.0, 0.999)) as u8;
-
-            // flip y-axis for correct rendering
-            let y = image_height - y - 1;
+        if rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+            if job.is_paused() {
+                job.resume();
+            } else {
+                job.pause();
+            }
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_C) {
+            job.cancel();
+        }
+        let moved = navigation.update(&rl);
+        if moved || rl.is_key_pressed(KeyboardKey::KEY_R) {
+            job.cancel();
+            let mut renderer = job.join();
+            if moved {
+                // Start over from the new viewpoint, progressively so a
+                // first pass shows up quickly
+                renderer.camera = navigation.settings().build(aspect_ratio);
+                active_budget = Some(budget.unwrap_or(progressive::Budget {
+                    samples: max_samples,
+                    time: None,
+                }));
+            }
+            let (sender, new_receiver) = mpsc::channel();
+            receiver = new_receiver;
+            framebuffer.clear();
+            job = start_render(renderer, Arc::clone(&framebuffer), active_budget, sender);
+            dirty.fill(false);
+            pixels_rendered = 0;
+            passes = 0;
+            finished = false;
+            rendering_complete = false;
+            render_time = None;
+            render_start_time = Instant::now();
+        }
 
-            image.draw_pixel(x as i32, y as i32, Color::new(red, green, blue, 255));
-            pixels_rendered += 1;
+        for update in receiver.try_iter() {
+            match update {
+                Update::Tile(index) => {
+                    dirty[index] = true;
+                    pixels_rendered += framebuffer.tiles()[index].area();
+                }
+                Update::Pass(count) => passes = count,
+                Update::Finished => finished = true,
+            }
         }
 
         let time_for_update = last_texture_update.elapsed() >= UPDATE_INTERVAL;
-        let just_completed = pixels_rendered >= total_pixels && !rendering_complete;
-        if !rendering_complete && (time_for_update || just_completed) {
-            texture = rl
-                .load_texture_from_image(&thread, &image)
-                .context("Failed to update texture")?;
+        let just_completed = finished && !rendering_complete;
+        let changed = framebuffer
+            .tiles()
+            .iter()
+            .zip(&dirty)
+            .filter(|(_, dirty)| **dirty)
+            .map(|(tile, _)| *tile)
+            .reduce(Tile::union);
+        if (time_for_update || just_completed)
+            && let Some(changed) = changed
+        {
+            for (index, tile) in framebuffer.tiles().iter().enumerate() {
+                if std::mem::take(&mut dirty[index]) {
+                    let colors = framebuffer.colors(index);
+                    draw_region(&mut rgba, image_width, *tile, &colors, &display);
+                }
+            }
+            upload_region(&texture, &rgba, image_width, changed, &mut upload);
             last_texture_update = Instant::now();
-
-            if just_completed {
-                rendering_complete = true;
-                render_time = Some(render_start_time.elapsed());
+        }
+        if just_completed {
+            rendering_complete = true;
+            render_time = Some(
+                render_start_time
+                    .elapsed()
+                    .saturating_sub(job.paused_time()),
+            );
+            let pixels = framebuffer.pixels();
+            let colors = outputs.colors((image_width, image_height), &pixels);
+            if outputs.denoiser.is_some() {
+                draw_region(
+                    &mut rgba,
+                    image_width,
+                    framebuffer.bounds(),
+                    &colors,
+                    &display,
+                );
+                upload_region(
+                    &texture,
+                    &rgba,
+                    image_width,
+                    framebuffer.bounds(),
+                    &mut upload,
+                );
             }
+            outputs.write((image_width, image_height), &pixels, &colors, max_samples)?;
         }
 
         let mut d = rl.begin_drawing(&thread);
@@ -144,12 +269,242 @@ fn main() -> anyhow::Result<()> {
                 d.draw_text("Rendering complete!", 10, 10, 20, Color::GREEN);
             }
         } else {
-            let progress = (pixels_rendered as f32 / total_pixels as f32) * 100.0;
+            let (state, color) = if job.is_cancelled() {
+                ("Cancelled", Color::RED)
+            } else if job.is_paused() {
+                ("Paused", Color::ORANGE)
+            } else {
+                ("Rendering", Color::YELLOW)
+            };
             text_buffer.clear();
-            write!(&mut text_buffer, "Rendering: {:.1}%", progress).ok();
-            d.draw_text(&text_buffer, 10, 10, 20, Color::YELLOW);
+            match active_budget {
+                Some(budget) => write!(
+                    &mut text_buffer,
+                    "{}: {}/{} spp",
+                    state, passes, budget.samples
+                )
+                .ok(),
+                None => {
+                    let progress = (pixels_rendered as f32 / total_pixels as f32) * 100.0;
+                    write!(&mut text_buffer, "{}: {:.1}%", state, progress).ok()
+                }
+            };
+            d.draw_text(&text_buffer, 10, 10, 20, color);
         }
     }
 
+    // Stop the workers before the window's end of the channel goes away
+    job.cancel();
+    job.join();
     Ok(())
 }
+
+/// Renders into `framebuffer` on a background thread, telling the window
+/// about each finished tile through `sender`: in passes of one sample per
+/// pixel with a `budget`, one pixel at a time to completion otherwise.
+fn start_render(
+    renderer: render::Renderer,
+    framebuffer: Arc<Framebuffer>,
+    budget: Option<progressive::Budget>,
+    sender: mpsc::Sender<Update>,
+) -> RenderJob {
+    RenderJob::spawn(renderer, move |renderer, control| {
+        let on_tile = |tile| {
+            sender.send(Update::Tile(tile)).ok();
+        };
+        match budget {
+            Some(budget) => {
+                progressive::render(renderer, &framebuffer, budget, control, on_tile, |passes| {
+                    sender.send(Update::Pass(passes)).ok();
+                });
+            }
+            None => {
+                renderer.render_tiles(&framebuffer, render::Work::Finish, control, on_tile);
+            }
+        }
+        if !control.is_cancelled() {
+            sender.send(Update::Finished).ok();
+        }
+    })
+}
+
+/// Writes the display colors of `region`, given row by row, into `rgba`,
+/// an image `width` pixels wide.
+fn draw_region(
+    rgba: &mut [u8],
+    width: u32,
+    region: Tile,
+    colors: &[Vec3],
+    display: &DisplayTransform,
+) {
+    for ((x, y), color) in region.pixels().zip(colors) {
+        let i = 4 * (y * width + x) as usize;
+        rgba[i..i + 3].copy_from_slice(&output::to_rgb8(display.apply(*color)));
+    }
+}
+
+/// Copies `region` of `rgba`, an image `width` pixels wide, to the same
+/// place in `texture`, packing it into `upload` first. The safe
+/// `update_texture_rec` insists on data the size of the whole texture, so
+/// this calls raylib directly.
+fn upload_region(texture: &Texture2D, rgba: &[u8], width: u32, region: Tile, upload: &mut Vec<u8>) {
+    upload.clear();
+    for row in region.y..region.y + region.height {
+        let start = 4 * (row * width + region.x) as usize;
+        upload.extend_from_slice(&rgba[start..start + 4 * region.width as usize]);
+    }
+    let rect = Rectangle::new(
+        region.x as f32,
+        region.y as f32,
+        region.width as f32,
+        region.height as f32,
+    );
+    // SAFETY: `upload` holds the RGBA8 pixels of `rect`, which lies inside
+    // the texture, in the texture's format
+    unsafe {
+        raylib::ffi::UpdateTextureRec(**texture, rect.into(), upload.as_ptr().cast());
+    }
+}
+
+/// Messages from the rendering thread to the window.
+enum Update {
+    /// Index of a tile written to the framebuffer.
+    Tile(usize),
+    /// Progressive pass completed, with the number of passes so far.
+    Pass(u32),
+    Finished,
+}
+
+fn render_headless(
+    renderer: &render::Renderer,
+    outputs: &Outputs,
+    budget: Option<progressive::Budget>,
+    tiling: Tiling,
+) -> anyhow::Result<()> {
+    let render_start_time = Instant::now();
+    let (image_width, image_height) = (renderer.image_width, renderer.image_height);
+    let framebuffer = Framebuffer::new(image_width, image_height, tiling);
+    let control = job::Control::default();
+
+    match budget {
+        Some(budget) => {
+            let passes =
+                progressive::render(renderer, &framebuffer, budget, &control, |_| {}, |_| {});
+            println!("Rendered {} passes", passes);
+        }
+        None => {
+            renderer.render_tiles(&framebuffer, render::Work::Finish, &control, |_| {});
+        }
+    }
+    let pixels = framebuffer.pixels();
+
+    let duration = render_start_time.elapsed();
+    println!(
+        "Rendering complete! Time: {}.{:03}s",
+        duration.as_secs(),
+        duration.subsec_millis()
+    );
+    if renderer.adaptive.is_some() {
+        let total: u64 = pixels.iter().map(|p| p.samples() as u64).sum();
+        println!(
+            "Average samples per pixel: {:.1}",
+            total as f64 / pixels.len() as f64
+        );
+    }
+
+    let colors = outputs.colors((image_width, image_height), &pixels);
+    outputs.write(
+        (image_width, image_height),
+        &pixels,
+        &colors,
+        renderer.samples_per_pixel,
+    )
+}
+
+/// Files written when the render finishes, and how the image is prepared
+/// for them.
+struct Outputs {
+    paths: Vec<PathBuf>,
+    /// Image of the sample count of each pixel, as a ramp from black for
+    /// none to white for the most samples a pixel can take.
+    sample_map: Option<PathBuf>,
+    /// Render passes written next to each of `paths`: as extra channels of
+    /// EXR files, as separate files named `<stem>.<pass>.<ext>` otherwise.
+    aovs: Vec<Aov>,
+    display: DisplayTransform,
+    denoiser: Option<Denoiser>,
+}
+
+impl Outputs {
+    /// Final colors of `pixels`, denoised if requested.
+    fn colors(&self, (width, height): (u32, u32), pixels: &[PixelEstimate]) -> Vec<Vec3> {
+        match &self.denoiser {
+            Some(denoiser) => denoiser.denoise(width, height, pixels),
+            None => pixels.iter().map(|p| p.mean()).collect(),
+        }
+    }
+
+    /// Writes every output of `pixels`, whose final colors are `colors`.
+    fn write(
+        &self,
+        (width, height): (u32, u32),
+        pixels: &[PixelEstimate],
+        colors: &[Vec3],
+        max_samples: u32,
+    ) -> anyhow::Result<()> {
+        let passes: Vec<(Aov, Vec<Vec3>)> = self
+            .aovs
+            .iter()
+            .map(|&aov| (aov, pixels.iter().map(|p| aov.value(p.aovs())).collect()))
+            .collect();
+        for path in &self.paths {
+            let format = output::OutputFormat::from_path(path)?;
+            if matches!(format, output::OutputFormat::Exr) && !passes.is_empty() {
+                let mut layers = vec![output::ExrLayer {
+                    name: "",
+                    channels: &["R", "G", "B"],
+                    pixels: colors.to_vec(),
+                }];
+                layers.extend(passes.iter().map(|(aov, values)| output::ExrLayer {
+                    name: aov.name(),
+                    channels: aov.channels(),
+                    pixels: values.clone(),
+                }));
+                output::write_exr_layers(path, width, height, &layers)?;
+                println!("Wrote {}", path.display());
+                continue;
+            }
+            output::write_image(path, width, height, colors, |c| self.display.apply(c))?;
+            println!("Wrote {}", path.display());
+            for (aov, values) in &passes {
+                let path = aov_path(path, *aov);
+                let visualize = aov.visualize(values, &self.display);
+                output::write_image(&path, width, height, values, visualize)?;
+                println!("Wrote {}", path.display());
+            }
+        }
+        if let Some(path) = &self.sample_map {
+            let counts: Vec<Vec3> = pixels
+                .iter()
+                .map(|p| {
+                    let level = p.samples() as f32 / max_samples as f32;
+                    Vec3::new(level, level, level)
+                })
+                .collect();
+            output::write_image(path, width, height, &counts, |level| level)?;
+            println!("Wrote {}", path.display());
+        }
+        Ok(())
+    }
+}
+
+/// `<stem>.<pass>.<ext>` next to `path`.
+fn aov_path(path: &Path, aov: Aov) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{}.{}", stem, aov.name());
+    if let Some(extension) = path.extension() {
+        name.push('.');
+        name.push_str(&extension.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
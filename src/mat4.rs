@@ -0,0 +1,184 @@
+use crate::vec3::Vec3;
+
+use std::ops::Mul;
+
+/// Row-major 4x4 matrix acting on column vectors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    m: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4 {
+        m: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ],
+    };
+
+    pub fn translation(offset: Vec3) -> Mat4 {
+        let mut m = Mat4::IDENTITY.m;
+        for (row, value) in m.iter_mut().zip([offset.x(), offset.y(), offset.z()]) {
+            row[3] = value;
+        }
+        Mat4 { m }
+    }
+
+    pub fn scaling(factors: Vec3) -> Mat4 {
+        let mut m = Mat4::IDENTITY.m;
+        for (i, value) in [factors.x(), factors.y(), factors.z()]
+            .into_iter()
+            .enumerate()
+        {
+            m[i][i] = value;
+        }
+        Mat4 { m }
+    }
+
+    /// Rotation by `degrees` around `axis`, counterclockwise when looking
+    /// down the axis towards the origin.
+    pub fn rotation(axis: Vec3, degrees: f32) -> Mat4 {
+        let a = axis.unit();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+        let (x, y, z) = (a.x(), a.y(), a.z());
+        Mat4 {
+            m: [
+                [
+                    t * x * x + cos,
+                    t * x * y - sin * z,
+                    t * x * z + sin * y,
+                    0.0,
+                ],
+                [
+                    t * x * y + sin * z,
+                    t * y * y + cos,
+                    t * y * z - sin * x,
+                    0.0,
+                ],
+                [
+                    t * x * z - sin * y,
+                    t * y * z + sin * x,
+                    t * z * z + cos,
+                    0.0,
+                ],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    /// Inverse by Gauss-Jordan elimination, `None` if the matrix is
+    /// singular.
+    pub fn inverse(&self) -> Option<Mat4> {
+        let mut a = self.m;
+        let mut inv = Mat4::IDENTITY.m;
+        for col in 0..4 {
+            let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+            let scale = 1.0 / a[col][col];
+            for k in 0..4 {
+                a[col][k] *= scale;
+                inv[col][k] *= scale;
+            }
+            for row in 0..4 {
+                let factor = a[row][col];
+                if row == col || factor == 0.0 {
+                    continue;
+                }
+                for k in 0..4 {
+                    a[row][k] -= factor * a[col][k];
+                    inv[row][k] -= factor * inv[col][k];
+                }
+            }
+        }
+        Some(Mat4 { m: inv })
+    }
+
+    #[inline]
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * p.x() + m[0][1] * p.y() + m[0][2] * p.z() + m[0][3],
+            m[1][0] * p.x() + m[1][1] * p.y() + m[1][2] * p.z() + m[1][3],
+            m[2][0] * p.x() + m[2][1] * p.y() + m[2][2] * p.z() + m[2][3],
+        )
+    }
+
+    #[inline]
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    }
+
+    /// Multiplies `v` by the transpose. Called on the inverse, this maps
+    /// normals, which must stay perpendicular to transformed surfaces.
+    #[inline]
+    pub fn transform_transposed(&self, v: Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3::new(
+            m[0][0] * v.x() + m[1][0] * v.y() + m[2][0] * v.z(),
+            m[0][1] * v.x() + m[1][1] * v.y() + m[2][1] * v.z(),
+            m[0][2] * v.x() + m[1][2] * v.y() + m[2][2] * v.z(),
+        )
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, other: Mat4) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * other.m[k][j]).sum();
+            }
+        }
+        Mat4 { m }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::assert_near;
+
+    /// Checks that the affine `m` leaves points and directions alone.
+    fn assert_identity(m: Mat4) {
+        let points = [
+            Vec3::ZERO,
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ];
+        for p in points {
+            assert_near(m.transform_point(p), p);
+            assert_near(m.transform_vector(p), p);
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_composite_transform() {
+        let m = Mat4::translation(Vec3::new(3.0, -2.0, 5.0))
+            * Mat4::rotation(Vec3::new(1.0, 2.0, -0.5), 37.0)
+            * Mat4::scaling(Vec3::new(2.0, 0.5, 3.0));
+        let inverse = m.inverse().expect("invertible");
+        assert_identity(m * inverse);
+        assert_identity(inverse * m);
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        let flat = Mat4::scaling(Vec3::new(1.0, 0.0, 1.0));
+        assert!(flat.inverse().is_none());
+    }
+}
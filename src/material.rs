@@ -1,59 +1,270 @@
 use crate::hittable::HitRecord;
+use crate::onb::Onb;
 use crate::ray::Ray;
+use crate::sampler::{Sampler, Samplers};
+use crate::texture::{Texture, Textures};
 use crate::vec3::Vec3;
-use rand::random;
 
 pub struct ScatterRecord {
+    /// BSDF times cosine divided by `pdf`, the weight of the scattered path.
     pub attenuation: Vec3,
     pub scattered: Ray,
+    /// Solid angle density of `scattered`, 0 for specular scattering.
+    pub pdf: f32,
+    /// Perfect mirrors and glass can't be lit through light sampling, so the
+    /// emission they see has to be picked up by the scattered ray.
+    pub is_specular: bool,
 }
 
 pub trait Material {
-    fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<ScatterRecord>;
+    fn scatter(&self, r_in: Ray, rec: &HitRecord, sampler: &mut Samplers) -> Option<ScatterRecord>;
+
+    /// Radiance emitted from the surface towards the incoming ray.
+    fn emitted(&self, _rec: &HitRecord) -> Vec3 {
+        Vec3::ZERO
+    }
+
+    /// BSDF times cosine for light arriving from `direction`, zero for
+    /// specular materials.
+    fn eval(&self, _r_in: Ray, _rec: &HitRecord, _direction: Vec3) -> Vec3 {
+        Vec3::ZERO
+    }
+
+    /// Density with which `scatter` picks `direction`, zero for specular
+    /// materials.
+    fn scattering_pdf(&self, _r_in: Ray, _rec: &HitRecord, _direction: Vec3) -> f32 {
+        0.0
+    }
+
+    /// Surface color at the hit, as a guide for denoising.
+    fn albedo(&self, _rec: &HitRecord) -> Vec3 {
+        Vec3::new(1.0, 1.0, 1.0)
+    }
 }
 
 #[derive(Clone)]
 pub enum Materials {
-    Lambertian { albedo: Vec3 },
-    Metal { albedo: Vec3, fuzz: f32 },
-    Dielectric { ir: f32 },
+    Lambertian {
+        albedo: Textures,
+    },
+    Metal {
+        albedo: Textures,
+        fuzz: f32,
+    },
+    Dielectric {
+        ir: f32,
+    },
+    DiffuseLight {
+        emit: Vec3,
+    },
+    /// Phase function of a medium scattering equally in every direction.
+    Isotropic {
+        albedo: Textures,
+    },
+    /// Phase function favoring forward scattering for `g > 0` and backward
+    /// scattering for `g < 0`.
+    HenyeyGreenstein {
+        albedo: Textures,
+        g: f32,
+    },
 }
 
 impl Material for Materials {
-    fn scatter(&self, r_in: Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+    fn scatter(&self, r_in: Ray, rec: &HitRecord, sampler: &mut Samplers) -> Option<ScatterRecord> {
+        match self {
+            Materials::Lambertian { albedo } => {
+                lambertian_scatter(albedo.value(rec.u, rec.v, rec.p), r_in, rec, sampler)
+            }
+            Materials::Metal { albedo, fuzz } => {
+                let albedo = albedo.value(rec.u, rec.v, rec.p);
+                metal_scatter(albedo, *fuzz, r_in, rec, sampler)
+            }
+            Materials::Dielectric { ir } => dielectric_scatter(*ir, r_in, rec, sampler),
+            Materials::DiffuseLight { .. } => None,
+            Materials::Isotropic { albedo } => Some(ScatterRecord {
+                attenuation: albedo.value(rec.u, rec.v, rec.p),
+                scattered: Ray::new(rec.p, Vec3::random_unit_vector(sampler), r_in.time),
+                pdf: 1.0 / (4.0 * std::f32::consts::PI),
+                is_specular: false,
+            }),
+            Materials::HenyeyGreenstein { albedo, g } => {
+                let albedo = albedo.value(rec.u, rec.v, rec.p);
+                henyey_greenstein_scatter(albedo, *g, r_in, rec, sampler)
+            }
+        }
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Vec3 {
+        match self {
+            // Lights only shine from their front side
+            Materials::DiffuseLight { emit } if rec.front_face => *emit,
+            _ => Vec3::ZERO,
+        }
+    }
+
+    fn eval(&self, r_in: Ray, rec: &HitRecord, direction: Vec3) -> Vec3 {
+        match self {
+            Materials::Lambertian { albedo } => {
+                albedo.value(rec.u, rec.v, rec.p) * self.scattering_pdf(r_in, rec, direction)
+            }
+            // The lobe is sampled exactly, so BSDF times cosine equals the
+            // pdf wherever the direction is above the surface
+            Materials::Metal { albedo, .. } if Vec3::dot(direction, rec.normal) > 0.0 => {
+                albedo.value(rec.u, rec.v, rec.p) * self.scattering_pdf(r_in, rec, direction)
+            }
+            // Phase functions are sampled exactly too and have no cosine
+            Materials::Isotropic { albedo } | Materials::HenyeyGreenstein { albedo, .. } => {
+                albedo.value(rec.u, rec.v, rec.p) * self.scattering_pdf(r_in, rec, direction)
+            }
+            _ => Vec3::ZERO,
+        }
+    }
+
+    fn scattering_pdf(&self, r_in: Ray, rec: &HitRecord, direction: Vec3) -> f32 {
+        match self {
+            Materials::Lambertian { .. } => {
+                Vec3::dot(rec.normal, direction.unit()).max(0.0) / std::f32::consts::PI
+            }
+            Materials::Metal { fuzz, .. } if *fuzz > 0.0 => {
+                let reflected = Vec3::reflect(r_in.direction.unit(), rec.normal);
+                let cos_alpha = Vec3::dot(reflected, direction.unit()).max(0.0);
+                let exponent = phong_exponent(*fuzz);
+                (exponent + 1.0) / (2.0 * std::f32::consts::PI) * cos_alpha.powf(exponent)
+            }
+            Materials::Isotropic { .. } => 1.0 / (4.0 * std::f32::consts::PI),
+            Materials::HenyeyGreenstein { g, .. } => {
+                let cos_theta = Vec3::dot(r_in.direction.unit(), direction.unit());
+                henyey_greenstein(*g, cos_theta)
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Vec3 {
         match self {
-            Materials::Lambertian { albedo } => lambertian_scatter(*albedo, r_in, rec),
-            Materials::Metal { albedo, fuzz } => metal_scatter(*albedo, *fuzz, r_in, rec),
-            Materials::Dielectric { ir } => dielectric_scatter(*ir, r_in, rec),
+            Materials::Lambertian { albedo }
+            | Materials::Metal { albedo, .. }
+            | Materials::Isotropic { albedo }
+            | Materials::HenyeyGreenstein { albedo, .. } => albedo.value(rec.u, rec.v, rec.p),
+            _ => Vec3::new(1.0, 1.0, 1.0),
         }
     }
 }
 
-fn lambertian_scatter(albedo: Vec3, _: Ray, rec: &HitRecord) -> Option<ScatterRecord> {
-    let mut scatter_direction = rec.normal + Vec3::random_unit_vector();
+impl Materials {
+    pub fn is_emissive(&self) -> bool {
+        matches!(self, Materials::DiffuseLight { .. })
+    }
+}
+
+fn lambertian_scatter(
+    albedo: Vec3,
+    r_in: Ray,
+    rec: &HitRecord,
+    sampler: &mut Samplers,
+) -> Option<ScatterRecord> {
+    let mut scatter_direction = rec.normal + Vec3::random_unit_vector(sampler);
     if scatter_direction.near_zero() {
         scatter_direction = rec.normal;
     }
+    // Normal plus a unit vector is cosine distributed, which cancels the
+    // cosine and 1/pi of the BSDF
+    let cosine = Vec3::dot(rec.normal, scatter_direction.unit());
     Some(ScatterRecord {
         attenuation: albedo,
-        scattered: Ray::new(rec.p, scatter_direction),
+        scattered: Ray::new(rec.p, scatter_direction, r_in.time),
+        pdf: cosine / std::f32::consts::PI,
+        is_specular: false,
     })
 }
 
-fn metal_scatter(albedo: Vec3, fuzz: f32, r_in: Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+fn metal_scatter(
+    albedo: Vec3,
+    fuzz: f32,
+    r_in: Ray,
+    rec: &HitRecord,
+    sampler: &mut Samplers,
+) -> Option<ScatterRecord> {
     let reflected = Vec3::reflect(r_in.direction.unit(), rec.normal);
-    let scattered = Ray::new(rec.p, reflected + fuzz * Vec3::random_in_unit_sphere());
-    if Vec3::dot(scattered.direction, rec.normal) > 0.0 {
-        Some(ScatterRecord {
+    if fuzz <= 0.0 {
+        return Some(ScatterRecord {
             attenuation: albedo,
-            scattered,
-        })
-    } else {
-        None
+            scattered: Ray::new(rec.p, reflected, r_in.time),
+            pdf: 0.0,
+            is_specular: true,
+        });
     }
+
+    // Phong lobe around the mirror direction, sampled proportionally to
+    // cos^n of the angle to it
+    let exponent = phong_exponent(fuzz);
+    let (u, v) = sampler.next_2d();
+    let cos_alpha = u.powf(1.0 / (exponent + 1.0));
+    let sin_alpha = (1.0 - cos_alpha * cos_alpha).max(0.0).sqrt();
+    let phi = 2.0 * std::f32::consts::PI * v;
+    let local = Vec3::new(phi.cos() * sin_alpha, phi.sin() * sin_alpha, cos_alpha);
+    let direction = Onb::new(reflected).local(local);
+    if Vec3::dot(direction, rec.normal) <= 0.0 {
+        return None;
+    }
+    Some(ScatterRecord {
+        attenuation: albedo,
+        scattered: Ray::new(rec.p, direction, r_in.time),
+        pdf: (exponent + 1.0) / (2.0 * std::f32::consts::PI) * cos_alpha.powf(exponent),
+        is_specular: false,
+    })
+}
+
+/// Phong exponent whose lobe is about as wide as a reflection perturbed by
+/// a sphere of radius `fuzz`.
+fn phong_exponent(fuzz: f32) -> f32 {
+    (2.0 / (fuzz * fuzz) - 2.0).max(0.0)
+}
+
+fn henyey_greenstein_scatter(
+    albedo: Vec3,
+    g: f32,
+    r_in: Ray,
+    rec: &HitRecord,
+    sampler: &mut Samplers,
+) -> Option<ScatterRecord> {
+    // Inverse of the phase function's CDF over the cosine between the
+    // incoming and scattered directions
+    let (u, v) = sampler.next_2d();
+    let cos_theta = if g.abs() < 1e-3 {
+        1.0 - 2.0 * u
+    } else {
+        let s = (1.0 - g * g) / (1.0 + g - 2.0 * g * u);
+        ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+    };
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * std::f32::consts::PI * v;
+    let local = Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta);
+    Some(ScatterRecord {
+        attenuation: albedo,
+        scattered: Ray::new(
+            rec.p,
+            Onb::new(r_in.direction.unit()).local(local),
+            r_in.time,
+        ),
+        pdf: henyey_greenstein(g, cos_theta),
+        is_specular: false,
+    })
+}
+
+/// Henyey-Greenstein phase function, a density over the sphere of
+/// directions.
+fn henyey_greenstein(g: f32, cos_theta: f32) -> f32 {
+    let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * std::f32::consts::PI * denom * denom.sqrt())
 }
 
-fn dielectric_scatter(ir: f32, r_in: Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+fn dielectric_scatter(
+    ir: f32,
+    r_in: Ray,
+    rec: &HitRecord,
+    sampler: &mut Samplers,
+) -> Option<ScatterRecord> {
     let refraction_ratio = if rec.front_face { 1.0 / ir } else { ir };
     let unit_direction = r_in.direction.unit();
     let cos_theta = Vec3::dot(-unit_direction, rec.normal).min(1.0);
@@ -64,13 +275,15 @@ fn dielectric_scatter(ir: f32, r_in: Ray, rec: &HitRecord) -> Option<ScatterReco
         r0 = r0 * r0;
         r0 + (1.0 - r0) * (1.0 - cos_theta).powf(5.0)
     };
-    let direction = if cannot_refract || reflectance > random() {
+    let direction = if cannot_refract || reflectance > sampler.next_1d() {
         Vec3::reflect(unit_direction, rec.normal)
     } else {
         Vec3::refract(unit_direction, rec.normal, refraction_ratio)
     };
     Some(ScatterRecord {
         attenuation: Vec3::new(1.0, 1.0, 1.0),
-        scattered: Ray::new(rec.p, direction),
+        scattered: Ray::new(rec.p, direction, r_in.time),
+        pdf: 0.0,
+        is_specular: true,
     })
 }
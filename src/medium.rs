@@ -0,0 +1,261 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, HittableEnum};
+use crate::material::Materials;
+use crate::perlin::Perlin;
+use crate::ray::Ray;
+use crate::sampler::{Sampler, Samplers};
+use crate::vec3::Vec3;
+
+use anyhow::{Context, anyhow, bail};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Volume of uniform density inside a convex boundary, or filling all of
+/// space when there is none. Rays scatter after an exponentially distributed
+/// distance, with the phase function given by `phase`.
+pub struct ConstantMedium {
+    boundary: Option<Box<HittableEnum>>,
+    density: f32,
+    phase: Materials,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: HittableEnum, density: f32, phase: Materials) -> ConstantMedium {
+        ConstantMedium {
+            boundary: Some(Box::new(boundary)),
+            density,
+            phase,
+        }
+    }
+
+    /// Medium without a boundary, such as atmospheric fog.
+    pub fn unbounded(density: f32, phase: Materials) -> ConstantMedium {
+        ConstantMedium {
+            boundary: None,
+            density,
+            phase,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32, sampler: &mut Samplers) -> Option<HitRecord<'_>> {
+        let (t0, t1) = match &self.boundary {
+            Some(boundary) => segment(boundary, r, t_min, t_max, sampler)?,
+            None => (t_min, t_max),
+        };
+        if self.density <= 0.0 {
+            return None;
+        }
+        let distance = -(1.0 - sampler.next_1d()).ln() / self.density;
+        let t = t0 + distance * r.direction.inv_length();
+        (t < t1).then(|| scattering(r, t, &self.phase))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        match &self.boundary {
+            Some(boundary) => boundary.bounding_box(),
+            None => Aabb::new(
+                Vec3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+                Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            ),
+        }
+    }
+}
+
+/// Volume whose density varies inside a convex boundary. Free paths are
+/// sampled with delta tracking against the maximum density.
+pub struct HeterogeneousMedium {
+    boundary: Box<HittableEnum>,
+    density: Density,
+    phase: Materials,
+    bounds: Aabb,
+    majorant: f32,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(boundary: HittableEnum, density: Density, phase: Materials) -> HeterogeneousMedium {
+        let bounds = boundary.bounding_box();
+        HeterogeneousMedium {
+            boundary: Box::new(boundary),
+            majorant: density.majorant(),
+            density,
+            phase,
+            bounds,
+        }
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(&self, r: Ray, t_min: f32, t_max: f32, sampler: &mut Samplers) -> Option<HitRecord<'_>> {
+        let (t0, t1) = segment(&self.boundary, r, t_min, t_max, sampler)?;
+        if self.majorant <= 0.0 {
+            return None;
+        }
+        let step = r.direction.inv_length() / self.majorant;
+        let mut t = t0;
+        loop {
+            // Tentative collision with the majorant, which is real with
+            // probability density / majorant and null otherwise
+            t -= (1.0 - sampler.next_1d()).ln() * step;
+            if t >= t1 {
+                return None;
+            }
+            let density = self.density.at(r.at(t), self.bounds);
+            if sampler.next_1d() * self.majorant < density {
+                return Some(scattering(r, t, &self.phase));
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+/// Density of a heterogeneous medium, per unit length.
+pub enum Density {
+    /// Grid stretched over the boundary's bounding box, scaled by `scale`.
+    Grid { grid: Arc<DensityGrid>, scale: f32 },
+    /// Perlin noise remapped to `[0, 1]`, scaled by `scale`.
+    Noise {
+        perlin: Arc<Perlin>,
+        frequency: f32,
+        scale: f32,
+    },
+}
+
+impl Density {
+    fn at(&self, p: Vec3, bounds: Aabb) -> f32 {
+        match self {
+            Density::Grid { grid, scale } => {
+                let extent = bounds.extent();
+                let local = p - bounds.min;
+                scale
+                    * grid.value(
+                        local.x() / extent.x(),
+                        local.y() / extent.y(),
+                        local.z() / extent.z(),
+                    )
+            }
+            Density::Noise {
+                perlin,
+                frequency,
+                scale,
+            } => scale * (0.5 * (1.0 + perlin.noise(*frequency * p))).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Upper bound of `at` anywhere in the medium.
+    fn majorant(&self) -> f32 {
+        match self {
+            Density::Grid { grid, scale } => scale * grid.max,
+            Density::Noise { scale, .. } => *scale,
+        }
+    }
+}
+
+/// Voxel densities, trilinearly interpolated between voxel centers.
+pub struct DensityGrid {
+    size: [usize; 3],
+    values: Vec<f32>,
+    max: f32,
+}
+
+impl DensityGrid {
+    /// Loads a text grid: the three dimensions followed by one value per
+    /// voxel, x varying fastest and z slowest. `#` starts a comment.
+    pub fn load(path: &Path) -> anyhow::Result<DensityGrid> {
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read density grid {}", path.display()))?;
+        DensityGrid::parse(&source)
+            .with_context(|| format!("Invalid density grid {}", path.display()))
+    }
+
+    fn parse(source: &str) -> anyhow::Result<DensityGrid> {
+        let mut tokens = source.lines().flat_map(|line| {
+            line.split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace()
+        });
+        let mut dimension = || -> anyhow::Result<usize> {
+            let token = tokens.next().ok_or_else(|| anyhow!("missing grid size"))?;
+            match token.parse() {
+                Ok(n) if n > 0 => Ok(n),
+                _ => bail!("invalid grid size '{}'", token),
+            }
+        };
+        let size = [dimension()?, dimension()?, dimension()?];
+        let values = tokens
+            .map(|token| {
+                token
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|v| *v >= 0.0)
+                    .ok_or_else(|| anyhow!("invalid density '{}'", token))
+            })
+            .collect::<anyhow::Result<Vec<f32>>>()?;
+        let expected = size[0] * size[1] * size[2];
+        if values.len() != expected {
+            bail!("expected {} densities, found {}", expected, values.len());
+        }
+        let max = values.iter().copied().fold(0.0, f32::max);
+        Ok(DensityGrid { size, values, max })
+    }
+
+    fn voxel(&self, x: i64, y: i64, z: i64) -> f32 {
+        let [nx, ny, nz] = self.size;
+        let x = x.clamp(0, nx as i64 - 1) as usize;
+        let y = y.clamp(0, ny as i64 - 1) as usize;
+        let z = z.clamp(0, nz as i64 - 1) as usize;
+        self.values[(z * ny + y) * nx + x]
+    }
+
+    /// Density at coordinates in `[0, 1]` across the grid.
+    fn value(&self, u: f32, v: f32, w: f32) -> f32 {
+        let [nx, ny, nz] = self.size;
+        let x = u * nx as f32 - 0.5;
+        let y = v * ny as f32 - 0.5;
+        let z = w * nz as f32 - 0.5;
+        let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+        let (tx, ty, tz) = (x - x0, y - y0, z - z0);
+        let (x0, y0, z0) = (x0 as i64, y0 as i64, z0 as i64);
+
+        let lerp = |a: f32, b: f32, t: f32| (1.0 - t) * a + t * b;
+        let row = |y, z| lerp(self.voxel(x0, y, z), self.voxel(x0 + 1, y, z), tx);
+        let slice = |z| lerp(row(y0, z), row(y0 + 1, z), ty);
+        lerp(slice(z0), slice(z0 + 1), tz)
+    }
+}
+
+/// Parametric interval where `r` is inside `boundary`, clipped to
+/// `[t_min, t_max]`. Rays that start inside get the part ahead of them.
+fn segment(
+    boundary: &HittableEnum,
+    r: Ray,
+    t_min: f32,
+    t_max: f32,
+    sampler: &mut Samplers,
+) -> Option<(f32, f32)> {
+    let enter = boundary.hit(r, f32::NEG_INFINITY, f32::INFINITY, sampler)?;
+    let exit = boundary.hit(r, enter.t + 1e-4, f32::INFINITY, sampler)?;
+    let t0 = enter.t.max(t_min);
+    let t1 = exit.t.min(t_max);
+    (t0 < t1).then_some((t0, t1))
+}
+
+/// Scattering event at `t` inside a medium. The normal has no meaning for
+/// phase functions and just faces the ray.
+fn scattering(r: Ray, t: f32, phase: &Materials) -> HitRecord<'_> {
+    HitRecord {
+        p: r.at(t),
+        normal: -r.direction.unit(),
+        mat: Some(phase),
+        t,
+        u: 0.0,
+        v: 0.0,
+        front_face: true,
+        object: 0,
+    }
+}
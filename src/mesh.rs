@@ -0,0 +1,209 @@
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Materials;
+use crate::ray::Ray;
+use crate::sampler::Samplers;
+use crate::texture::{ImageTexture, Textures, WrapMode};
+use crate::triangle;
+use crate::vec3::Vec3;
+
+use anyhow::{Context, bail};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Indexed triangle mesh with its own BVH over the faces.
+pub struct Mesh {
+    positions: Vec<Vec3>,
+    /// Per-vertex normals, zero for vertices that have none.
+    normals: Vec<Vec3>,
+    uvs: Vec<(f32, f32)>,
+    faces: Vec<Face>,
+    materials: Vec<Materials>,
+    bvh: Bvh,
+}
+
+struct Face {
+    vertices: [u32; 3],
+    material: u32,
+    /// Whether the vertices have texture coordinates, otherwise the
+    /// barycentric coordinates are used.
+    textured: bool,
+}
+
+impl Mesh {
+    /// Loads a Wavefront OBJ file, triangulating its polygons. Materials come
+    /// from the MTL libraries it references unless `material` overrides them;
+    /// faces without one are light grey.
+    pub fn load_obj(path: &Path, material: Option<Materials>) -> anyhow::Result<Mesh> {
+        let (models, mtl_materials) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)
+            .with_context(|| format!("Failed to load mesh {}", path.display()))?;
+
+        let materials = match material {
+            Some(material) => vec![material],
+            None => {
+                let mtl_materials = mtl_materials
+                    .with_context(|| format!("Failed to load materials for {}", path.display()))?;
+                let base_dir = path.parent().unwrap_or(Path::new(""));
+                let mut materials = mtl_materials
+                    .iter()
+                    .map(|m| mtl_material(m, base_dir))
+                    .collect::<anyhow::Result<Vec<Materials>>>()?;
+                materials.push(Materials::Lambertian {
+                    albedo: Textures::Solid(Vec3::new(0.8, 0.8, 0.8)),
+                });
+                materials
+            }
+        };
+        let default_material = materials.len() - 1;
+
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut uvs = Vec::new();
+        let mut faces = Vec::new();
+        for model in &models {
+            let m = &model.mesh;
+            let offset = positions.len() as u32;
+            let vertex_count = m.positions.len() / 3;
+            let textured = m.texcoords.len() == vertex_count * 2;
+            for i in 0..vertex_count {
+                positions.push(Vec3::new(
+                    m.positions[3 * i],
+                    m.positions[3 * i + 1],
+                    m.positions[3 * i + 2],
+                ));
+                normals.push(if m.normals.len() == vertex_count * 3 {
+                    Vec3::new(m.normals[3 * i], m.normals[3 * i + 1], m.normals[3 * i + 2])
+                } else {
+                    Vec3::ZERO
+                });
+                uvs.push(if textured {
+                    (m.texcoords[2 * i], m.texcoords[2 * i + 1])
+                } else {
+                    (0.0, 0.0)
+                });
+            }
+            let material = m
+                .material_id
+                .filter(|&id| id < default_material)
+                .unwrap_or(default_material);
+            for face in m.indices.chunks_exact(3) {
+                faces.push(Face {
+                    vertices: [face[0] + offset, face[1] + offset, face[2] + offset],
+                    material: material as u32,
+                    textured,
+                });
+            }
+        }
+        if faces.is_empty() {
+            bail!("Mesh {} has no faces", path.display());
+        }
+
+        let bounds: Vec<Aabb> = faces
+            .iter()
+            .map(|face| {
+                let [a, b, c] = face.vertices.map(|v| positions[v as usize]);
+                Aabb::new(a, b).grow(c).pad(1e-4)
+            })
+            .collect();
+        Ok(Mesh {
+            positions,
+            normals,
+            uvs,
+            faces,
+            materials,
+            bvh: Bvh::new(&bounds),
+        })
+    }
+
+    fn hit_face(&self, index: usize, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let face = &self.faces[index];
+        let [i0, i1, i2] = face.vertices.map(|v| v as usize);
+        let vertices = [self.positions[i0], self.positions[i1], self.positions[i2]];
+        let (t, barycentric) = triangle::intersect(vertices, r, t_min, t_max)?;
+        let [b0, b1, b2] = barycentric;
+
+        let uv = if face.textured {
+            let [uv0, uv1, uv2] = [self.uvs[i0], self.uvs[i1], self.uvs[i2]];
+            (
+                b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0,
+                b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1,
+            )
+        } else {
+            (b1, b2)
+        };
+        let [a, b, c] = vertices;
+        let geometric_normal = Vec3::cross(b - a, c - a).unit();
+        let mat = &self.materials[face.material as usize];
+        let mut rec = HitRecord::new(r, t, geometric_normal, uv, mat);
+        let shading = b0 * self.normals[i0] + b1 * self.normals[i1] + b2 * self.normals[i2];
+        triangle::set_shading_normal(&mut rec, geometric_normal, shading);
+        Some(rec)
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut Samplers,
+    ) -> Option<HitRecord<'_>> {
+        self.bvh.traverse(r, t_min, t_max, |i, r, t_min, t_max| {
+            self.hit_face(i, r, t_min, t_max)
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounds()
+    }
+}
+
+/// Maps an MTL material onto the closest material the renderer supports.
+/// Texture maps are resolved relative to `base_dir`.
+fn mtl_material(material: &tobj::Material, base_dir: &Path) -> anyhow::Result<Materials> {
+    let color = |c: Option<[f32; 3]>| c.map(|[r, g, b]| Vec3::new(r, g, b));
+    let diffuse = match &material.diffuse_texture {
+        Some(file) => Textures::Image(Arc::new(ImageTexture::load(
+            &base_dir.join(file),
+            WrapMode::Repeat,
+        )?)),
+        None => Textures::Solid(color(material.diffuse).unwrap_or(Vec3::new(0.8, 0.8, 0.8))),
+    };
+
+    let emission = material
+        .unknown_param
+        .get("Ke")
+        .and_then(|ke| {
+            let channels: Vec<f32> = ke
+                .split_whitespace()
+                .filter_map(|c| c.parse().ok())
+                .collect();
+            match channels[..] {
+                [r, g, b] => Some(Vec3::new(r, g, b)),
+                _ => None,
+            }
+        })
+        .filter(|emit| !emit.near_zero());
+    if let Some(emit) = emission {
+        return Ok(Materials::DiffuseLight { emit });
+    }
+
+    let illum = material.illumination_model.unwrap_or(2);
+    let transparent = material.dissolve.is_some_and(|d| d < 1.0);
+    if transparent || matches!(illum, 4 | 6 | 7) {
+        return Ok(Materials::Dielectric {
+            ir: material.optical_density.unwrap_or(1.5),
+        });
+    }
+    if matches!(illum, 3 | 5) {
+        // Inverse of `material::phong_exponent`
+        let shininess = material.shininess.unwrap_or(1000.0).max(0.0);
+        return Ok(Materials::Metal {
+            albedo: color(material.specular).map_or(diffuse, Textures::Solid),
+            fuzz: (2.0 / (shininess + 2.0)).sqrt(),
+        });
+    }
+    Ok(Materials::Lambertian { albedo: diffuse })
+}
@@ -0,0 +1,153 @@
+use crate::camera::CameraSettings;
+use crate::mat4::Mat4;
+use crate::vec3::Vec3;
+
+use raylib::prelude::*;
+
+/// Degrees of rotation per pixel of mouse movement.
+const ROTATE_SPEED: f32 = 0.25;
+/// Pan distance per pixel, as a fraction of the distance to the look-at
+/// point.
+const PAN_SPEED: f32 = 0.002;
+/// Distance change per wheel step.
+const DOLLY_FACTOR: f32 = 0.9;
+/// Fly speed, in distances to the look-at point per second.
+const FLY_SPEED: f32 = 0.5;
+
+/// Viewer camera controls:
+///
+/// - left drag orbits around the look-at point
+/// - middle drag, or shift and left drag, pans
+/// - the wheel dollies towards the look-at point
+/// - right drag looks around from where the camera stands
+/// - WASD flies forward, left, back and right, Q and E down and up, faster
+///   with shift
+pub struct Navigation {
+    settings: CameraSettings,
+}
+
+impl Navigation {
+    pub fn new(settings: CameraSettings) -> Navigation {
+        Navigation { settings }
+    }
+
+    pub fn settings(&self) -> &CameraSettings {
+        &self.settings
+    }
+
+    /// Applies this frame's mouse and keyboard input, returning whether the
+    /// camera moved.
+    pub fn update(&mut self, rl: &RaylibHandle) -> bool {
+        let delta = rl.get_mouse_delta();
+        let dragged = delta.x != 0.0 || delta.y != 0.0;
+        let shift = rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
+            || rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+        let left = rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT);
+        let mut moved = false;
+
+        if dragged && left && !shift {
+            self.orbit(-delta.x * ROTATE_SPEED, -delta.y * ROTATE_SPEED);
+            moved = true;
+        } else if dragged
+            && (rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_MIDDLE) || left && shift)
+        {
+            self.pan(delta.x, delta.y);
+            moved = true;
+        }
+        if dragged && rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_RIGHT) {
+            self.look(-delta.x * ROTATE_SPEED, -delta.y * ROTATE_SPEED);
+            moved = true;
+        }
+
+        let wheel = rl.get_mouse_wheel_move();
+        if wheel != 0.0 {
+            self.dolly(DOLLY_FACTOR.powf(wheel));
+            moved = true;
+        }
+
+        let keys = [
+            (KeyboardKey::KEY_W, Vec3::new(0.0, 0.0, 1.0)),
+            (KeyboardKey::KEY_S, Vec3::new(0.0, 0.0, -1.0)),
+            (KeyboardKey::KEY_D, Vec3::new(1.0, 0.0, 0.0)),
+            (KeyboardKey::KEY_A, Vec3::new(-1.0, 0.0, 0.0)),
+            (KeyboardKey::KEY_E, Vec3::new(0.0, 1.0, 0.0)),
+            (KeyboardKey::KEY_Q, Vec3::new(0.0, -1.0, 0.0)),
+        ];
+        let direction = keys
+            .iter()
+            .filter(|(key, _)| rl.is_key_down(*key))
+            .fold(Vec3::ZERO, |sum, (_, d)| sum + *d);
+        if !direction.near_zero() {
+            let speed = if shift { 4.0 * FLY_SPEED } else { FLY_SPEED };
+            self.fly(direction * speed * rl.get_frame_time());
+            moved = true;
+        }
+        moved
+    }
+
+    /// Unit vectors pointing forward, right and up from the camera.
+    fn basis(&self) -> (Vec3, Vec3, Vec3) {
+        let forward = (self.settings.lookat - self.settings.lookfrom).unit();
+        let right = Vec3::cross(forward, self.settings.vup).unit();
+        let up = Vec3::cross(right, forward);
+        (forward, right, up)
+    }
+
+    fn distance(&self) -> f32 {
+        (self.settings.lookat - self.settings.lookfrom).length()
+    }
+
+    /// Rotates `v` by `yaw` degrees around the up vector and `pitch` degrees
+    /// around `right`, unless that would tip it over the pole.
+    fn rotate(&self, v: Vec3, right: Vec3, yaw: f32, pitch: f32) -> Vec3 {
+        let vup = self.settings.vup;
+        let turned = Mat4::rotation(vup, yaw).transform_vector(v);
+        let tilted = Mat4::rotation(right, pitch).transform_vector(turned);
+        if Vec3::dot(tilted.unit(), vup.unit()).abs() > 0.99 {
+            turned
+        } else {
+            tilted
+        }
+    }
+
+    fn orbit(&mut self, yaw: f32, pitch: f32) {
+        let (_, right, _) = self.basis();
+        let offset = self.settings.lookfrom - self.settings.lookat;
+        self.settings.lookfrom = self.settings.lookat + self.rotate(offset, right, yaw, pitch);
+    }
+
+    fn look(&mut self, yaw: f32, pitch: f32) {
+        let (_, right, _) = self.basis();
+        let view = self.settings.lookat - self.settings.lookfrom;
+        self.settings.lookat = self.settings.lookfrom + self.rotate(view, right, yaw, pitch);
+    }
+
+    fn pan(&mut self, dx: f32, dy: f32) {
+        let (_, right, up) = self.basis();
+        let scale = PAN_SPEED * self.distance();
+        let shift = (-dx * right + dy * up) * scale;
+        self.settings.lookfrom += shift;
+        self.settings.lookat += shift;
+    }
+
+    /// Scales the distance to the look-at point by `factor`, and the focus
+    /// distance with it.
+    fn dolly(&mut self, factor: f32) {
+        let offset = self.settings.lookfrom - self.settings.lookat;
+        if offset.length() * factor < 1e-3 {
+            return;
+        }
+        self.settings.lookfrom = self.settings.lookat + offset * factor;
+        self.settings.focus_dist *= factor;
+    }
+
+    /// Moves the camera and the look-at point by `direction`, given in
+    /// camera space with z forward, scaled by the distance between them.
+    fn fly(&mut self, direction: Vec3) {
+        let (forward, right, up) = self.basis();
+        let shift = (direction.x() * right + direction.y() * up + direction.z() * forward)
+            * self.distance();
+        self.settings.lookfrom += shift;
+        self.settings.lookat += shift;
+    }
+}
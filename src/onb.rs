@@ -0,0 +1,28 @@
+use crate::vec3::Vec3;
+
+/// Orthonormal basis with `w` along a given direction.
+pub struct Onb {
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+}
+
+impl Onb {
+    pub fn new(n: Vec3) -> Onb {
+        let w = n.unit();
+        let a = if w.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = Vec3::cross(w, a).unit();
+        let u = Vec3::cross(w, v);
+        Onb { u, v, w }
+    }
+
+    /// Converts coordinates in this basis to world space.
+    #[inline]
+    pub fn local(&self, a: Vec3) -> Vec3 {
+        a.x() * self.u + a.y() * self.v + a.z() * self.w
+    }
+}
@@ -0,0 +1,179 @@
+use crate::vec3::Vec3;
+
+use anyhow::{Context, anyhow, bail};
+use image::ImageFormat;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Quantizes a display-encoded color to 8 bits per channel.
+#[inline]
+pub fn to_rgb8(pixel: Vec3) -> [u8; 3] {
+    let quantize = |c: f32| (255.0 * c.clamp(0.0, 1.0)).round() as u8;
+    [
+        quantize(pixel.x()),
+        quantize(pixel.y()),
+        quantize(pixel.z()),
+    ]
+}
+
+/// Packs `pixels`, stored row by row from the top of the image, as RGB bytes.
+fn rgb8_buffer(pixels: &[Vec3]) -> Vec<u8> {
+    pixels.iter().flat_map(|p| to_rgb8(*p)).collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum OutputFormat {
+    Png,
+    Ppm,
+    /// Portable float map, 32-bit float RGB
+    Pfm,
+    /// Radiance RGBE
+    Hdr,
+    /// OpenEXR, 32-bit float RGB
+    Exr,
+}
+
+impl OutputFormat {
+    pub fn from_path(path: &Path) -> anyhow::Result<OutputFormat> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("png") => Ok(OutputFormat::Png),
+            Some("ppm") => Ok(OutputFormat::Ppm),
+            Some("pfm") => Ok(OutputFormat::Pfm),
+            Some("hdr") => Ok(OutputFormat::Hdr),
+            Some("exr") => Ok(OutputFormat::Exr),
+            _ => bail!(
+                "Unsupported output format for {}, expected .png, .ppm, .pfm, .hdr or .exr",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Writes `pixels` to `path` in the format given by its extension. Float
+/// formats get the linear values as they are, 8-bit formats get them
+/// through `display` and then quantized.
+pub fn write_image(
+    path: &Path,
+    width: u32,
+    height: u32,
+    pixels: &[Vec3],
+    display: impl Fn(Vec3) -> Vec3,
+) -> anyhow::Result<()> {
+    let display_pixels = || pixels.iter().map(|p| display(*p)).collect::<Vec<_>>();
+    match OutputFormat::from_path(path)? {
+        OutputFormat::Png => write_png(path, width, height, &display_pixels()),
+        OutputFormat::Ppm => write_ppm(path, width, height, &display_pixels()),
+        OutputFormat::Pfm => write_pfm(path, width, height, pixels),
+        OutputFormat::Hdr => write_float(path, width, height, pixels, ImageFormat::Hdr),
+        OutputFormat::Exr => write_float(path, width, height, pixels, ImageFormat::OpenExr),
+    }
+}
+
+fn write_png(path: &Path, width: u32, height: u32, pixels: &[Vec3]) -> anyhow::Result<()> {
+    image::save_buffer_with_format(
+        path,
+        &rgb8_buffer(pixels),
+        width,
+        height,
+        image::ColorType::Rgb8,
+        ImageFormat::Png,
+    )
+    .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn write_ppm(path: &Path, width: u32, height: u32, pixels: &[Vec3]) -> anyhow::Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+    out.write_all(&rgb8_buffer(pixels))?;
+    out.flush()
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Writes a PFM, which stores rows from the bottom of the image up.
+fn write_pfm(path: &Path, width: u32, height: u32, pixels: &[Vec3]) -> anyhow::Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    // A negative scale marks the data as little endian
+    write!(out, "PF\n{} {}\n-1.0\n", width, height)?;
+    for row in pixels.chunks(width as usize).rev() {
+        for pixel in row {
+            for channel in [pixel.x(), pixel.y(), pixel.z()] {
+                out.write_all(&channel.to_le_bytes())?;
+            }
+        }
+    }
+    out.flush()
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn write_float(
+    path: &Path,
+    width: u32,
+    height: u32,
+    pixels: &[Vec3],
+    format: ImageFormat,
+) -> anyhow::Result<()> {
+    let data = pixels.iter().flat_map(|p| [p.x(), p.y(), p.z()]).collect();
+    let buffer = image::Rgb32FImage::from_raw(width, height, data).ok_or_else(|| {
+        anyhow!(
+            "{} pixels don't fill a {}x{} image",
+            pixels.len(),
+            width,
+            height
+        )
+    })?;
+    buffer
+        .save_with_format(path, format)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Group of channels in a multi-channel EXR, named `<name>.<channel>`, or
+/// just `<channel>` when the name is empty.
+pub struct ExrLayer<'a> {
+    pub name: &'a str,
+    /// Names of the first one to three components of `pixels`.
+    pub channels: &'a [&'a str],
+    pub pixels: Vec<Vec3>,
+}
+
+/// Writes `layers` as 32-bit float channels of a single EXR image.
+pub fn write_exr_layers(
+    path: &Path,
+    width: u32,
+    height: u32,
+    layers: &[ExrLayer],
+) -> anyhow::Result<()> {
+    use exr::prelude::*;
+
+    let channels: SmallVec<[AnyChannel<FlatSamples>; 4]> = layers
+        .iter()
+        .flat_map(|layer| {
+            layer.channels.iter().enumerate().map(move |(c, channel)| {
+                let name = match layer.name {
+                    "" => channel.to_string(),
+                    name => format!("{}.{}", name, channel),
+                };
+                let samples = layer.pixels.iter().map(|p| p[c]).collect();
+                AnyChannel::new(name.as_str(), FlatSamples::F32(samples))
+            })
+        })
+        .collect();
+    let layer = Layer::new(
+        (width as usize, height as usize),
+        LayerAttributes::default(),
+        Encoding::SMALL_LOSSLESS,
+        AnyChannels::sort(channels),
+    );
+    Image::from_layer(layer)
+        .write()
+        .to_file(path)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
@@ -0,0 +1,97 @@
+use crate::sampler::Pcg;
+use crate::vec3::Vec3;
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise with random unit vectors on the lattice points.
+pub struct Perlin {
+    ranvec: [Vec3; POINT_COUNT],
+    perm_x: [u8; POINT_COUNT],
+    perm_y: [u8; POINT_COUNT],
+    perm_z: [u8; POINT_COUNT],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        let mut sampler = Pcg::new(seed);
+        let ranvec = [(); POINT_COUNT].map(|_| Vec3::random_unit_vector(&mut sampler));
+        Perlin {
+            ranvec,
+            perm_x: generate_perm(&mut sampler),
+            perm_y: generate_perm(&mut sampler),
+            perm_z: generate_perm(&mut sampler),
+        }
+    }
+
+    /// Noise value in roughly `[-1, 1]`.
+    pub fn noise(&self, p: Vec3) -> f32 {
+        let u = p.x() - p.x().floor();
+        let v = p.y() - p.y().floor();
+        let w = p.z() - p.z().floor();
+        let i = p.x().floor() as i32;
+        let j = p.y().floor() as i32;
+        let k = p.z().floor() as i32;
+
+        let mut c = [[[Vec3::ZERO; 2]; 2]; 2];
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    let index = self.perm_x[((i + di as i32) & 255) as usize]
+                        ^ self.perm_y[((j + dj as i32) & 255) as usize]
+                        ^ self.perm_z[((k + dk as i32) & 255) as usize];
+                    *corner = self.ranvec[index as usize];
+                }
+            }
+        }
+        perlin_interp(&c, u, v, w)
+    }
+
+    /// Sum of `depth` octaves of noise, each at double the frequency and half
+    /// the weight of the previous one.
+    pub fn turbulence(&self, p: Vec3, depth: u32) -> f32 {
+        let mut accum = 0.0;
+        let mut temp_p = p;
+        let mut weight = 1.0;
+        for _ in 0..depth {
+            accum += weight * self.noise(temp_p);
+            weight *= 0.5;
+            temp_p *= 2.0;
+        }
+        accum.abs()
+    }
+}
+
+fn generate_perm(sampler: &mut Pcg) -> [u8; POINT_COUNT] {
+    let mut p = [0u8; POINT_COUNT];
+    for (i, value) in p.iter_mut().enumerate() {
+        *value = i as u8;
+    }
+    // Fisher–Yates shuffle
+    for i in (1..POINT_COUNT).rev() {
+        let target = (sampler.next_u32() as usize) % (i + 1);
+        p.swap(i, target);
+    }
+    p
+}
+
+fn perlin_interp(c: &[[[Vec3; 2]; 2]; 2], u: f32, v: f32, w: f32) -> f32 {
+    // Hermite smoothing hides the lattice
+    let uu = u * u * (3.0 - 2.0 * u);
+    let vv = v * v * (3.0 - 2.0 * v);
+    let ww = w * w * (3.0 - 2.0 * w);
+
+    let mut accum = 0.0;
+    for (i, plane) in c.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, corner) in row.iter().enumerate() {
+                let (fi, fj, fk) = (i as f32, j as f32, k as f32);
+                let weight_v = Vec3::new(u - fi, v - fj, w - fk);
+                accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                    * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                    * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                    * Vec3::dot(*corner, weight_v);
+            }
+        }
+    }
+    accum
+}
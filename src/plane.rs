@@ -0,0 +1,68 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Materials;
+use crate::ray::Ray;
+use crate::sampler::Samplers;
+use crate::vec3::Vec3;
+
+/// Infinite plane through `point`. Texture coordinates are distances along
+/// two tangent directions, so textures repeat in world units.
+pub struct Plane {
+    point: Vec3,
+    normal: Vec3,
+    tangent: Vec3,
+    bitangent: Vec3,
+    mat: Materials,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, m: Materials) -> Plane {
+        let normal = normal.unit();
+        let helper = if normal.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let bitangent = Vec3::cross(normal, helper).unit();
+        let tangent = Vec3::cross(bitangent, normal);
+        Plane {
+            point,
+            normal,
+            tangent,
+            bitangent,
+            mat: m,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut Samplers,
+    ) -> Option<HitRecord<'_>> {
+        let denom = Vec3::dot(self.normal, r.direction);
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+        let t = Vec3::dot(self.point - r.origin, self.normal) / denom;
+        if t < t_min || t_max < t {
+            return None;
+        }
+        let offset = r.at(t) - self.point;
+        let uv = (
+            Vec3::dot(offset, self.tangent),
+            Vec3::dot(offset, self.bitangent),
+        );
+        Some(HitRecord::new(r, t, self.normal, uv, &self.mat))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb {
+            min: Vec3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+            max: Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use crate::framebuffer::Framebuffer;
+use crate::job::Control;
+use crate::render::{Renderer, Work};
+
+use std::time::{Duration, Instant};
+
+/// When a progressive render stops.
+#[derive(Debug, Clone, Copy)]
+pub struct Budget {
+    /// Passes of one sample per pixel to render at most. Pixels that
+    /// adaptive sampling finds converged sit out the remaining passes.
+    pub samples: u32,
+    /// Rendering time, not counting pauses, after which no new pass is
+    /// started.
+    pub time: Option<Duration>,
+}
+
+/// Adds passes of one sample per pixel to `framebuffer` until `budget`
+/// runs out or every pixel has converged, calling `on_tile` after each tile
+/// and `on_pass` with the number of passes after each pass. The first pass
+/// always completes, so there is an image to show, unless `control` is
+/// cancelled, which abandons the pass in progress. Returns the number of
+/// completed passes.
+pub fn render(
+    renderer: &Renderer,
+    framebuffer: &Framebuffer,
+    budget: Budget,
+    control: &Control,
+    on_tile: impl Fn(usize) + Sync,
+    mut on_pass: impl FnMut(u32),
+) -> u32 {
+    let start = Instant::now();
+    let mut passes = 0;
+    loop {
+        let sampled = renderer.render_tiles(framebuffer, Work::Refine, control, &on_tile);
+        if control.is_cancelled() {
+            return passes;
+        }
+        passes += 1;
+        on_pass(passes);
+        let active = start.elapsed().saturating_sub(control.paused_time());
+        let out_of_time = budget.time.is_some_and(|limit| active >= limit);
+        if passes >= budget.samples || sampled == 0 || out_of_time {
+            return passes;
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Materials;
+use crate::ray::Ray;
+use crate::sampler::{Sampler, Samplers};
+use crate::vec3::Vec3;
+
+/// Parallelogram spanned by the edges `u` and `v` from the corner `q`.
+pub struct Quad {
+    q: Vec3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    d: f32,
+    w: Vec3,
+    area: f32,
+    mat: Materials,
+}
+
+impl Quad {
+    pub fn new(q: Vec3, u: Vec3, v: Vec3, m: Materials) -> Quad {
+        let n = Vec3::cross(u, v);
+        let normal = n.unit();
+        Quad {
+            q,
+            u,
+            v,
+            normal,
+            d: Vec3::dot(normal, q),
+            w: n / Vec3::dot(n, n),
+            area: n.length(),
+            mat: m,
+        }
+    }
+
+    pub fn material(&self) -> &Materials {
+        &self.mat
+    }
+
+    /// `t` and edge coordinates where `r` crosses the quad, if it does in
+    /// `[t_min, t_max]`.
+    fn intersect(&self, r: Ray, t_min: f32, t_max: f32) -> Option<(f32, (f32, f32))> {
+        let denom = Vec3::dot(self.normal, r.direction);
+        // The ray is parallel to the plane
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+        let t = (self.d - Vec3::dot(self.normal, r.origin)) / denom;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        // Planar coordinates of the hit point along the two edges
+        let planar_hitpt = r.at(t) - self.q;
+        let alpha = Vec3::dot(self.w, Vec3::cross(planar_hitpt, self.v));
+        let beta = Vec3::dot(self.w, Vec3::cross(self.u, planar_hitpt));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+        Some((t, (alpha, beta)))
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut Samplers,
+    ) -> Option<HitRecord<'_>> {
+        let (t, uv) = self.intersect(r, t_min, t_max)?;
+        Some(HitRecord::new(r, t, self.normal, uv, &self.mat))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::new(self.q, self.q + self.u + self.v)
+            .union(Aabb::new(self.q + self.u, self.q + self.v))
+            .pad(1e-4)
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f32 {
+        let Some((t, _)) = self.intersect(Ray::new(origin, direction, 0.0), 0.001, f32::INFINITY)
+        else {
+            return 0.0;
+        };
+        area_pdf(self.area, t, direction, self.normal)
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut Samplers) -> Vec3 {
+        let (s, t) = sampler.next_2d();
+        let p = self.q + s * self.u + t * self.v;
+        p - origin
+    }
+}
+
+/// Converts the density of picking a point uniformly on a surface of `area`
+/// to a solid angle density, for a hit at distance `t` along `direction`.
+pub fn area_pdf(area: f32, t: f32, direction: Vec3, normal: Vec3) -> f32 {
+    let distance_squared = t * t * direction.length_squared();
+    let cosine = (Vec3::dot(direction, normal) * direction.inv_length()).abs();
+    if cosine < 1e-6 {
+        return 0.0;
+    }
+    distance_squared / (cosine * area)
+}
@@ -1,58 +1,273 @@
 use crate::{
+    adaptive::{Adaptive, PixelEstimate},
+    aov::Aovs,
+    background::Background,
     camera::Camera,
-    hittable::{Hittable, HittableList},
-    material::Material,
+    framebuffer::Framebuffer,
+    hittable::{HitRecord, Hittable, HittableList},
+    job::Control,
+    material::{Material, Materials},
     ray::Ray,
+    sampler::{Sampler, SamplerKind, Samplers},
     vec3::Vec3,
 };
-use rand::random;
-
-pub fn render_pixel(
-    (x, y): (u32, u32),
-    (width, height): (u32, u32),
-    cam: &Camera,
-    world: &HittableList,
-    max_depth: u32,
-    samples_per_pixel: u32,
-) -> Vec3 {
-    let mut pixel_color = Vec3::empty();
-
-    // Pre-compute reciprocals and constants
-    let inv_width_minus_one = 1.0 / (width as f32 - 1.0);
-    let inv_height_minus_one = 1.0 / (height as f32 - 1.0);
-    let x_f32 = x as f32;
-    let y_f32 = y as f32;
-    let inv_samples = 1.0 / samples_per_pixel as f32;
-
-    for _ in 0..samples_per_pixel {
-        let u_rng: f32 = random();
-        let v_rng: f32 = random();
-        let u = (x_f32 + u_rng) * inv_width_minus_one;
-        let v = (y_f32 + v_rng) * inv_height_minus_one;
-        let r = cam.get_ray(u, v);
-        pixel_color += ray_color(r, world, max_depth);
-    }
-
-    pixel_color *= inv_samples;
-
-    // Apply gamma correction in place
-    let r = pixel_color.x().sqrt();
-    let g = pixel_color.y().sqrt();
-    let b = pixel_color.z().sqrt();
-    Vec3::new(r, g, b)
+
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Radiance of one path through a pixel, with its render passes.
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub color: Vec3,
+    pub aovs: Aovs,
+}
+
+/// What rendering a tile does to each of its pixels.
+#[derive(Debug, Clone, Copy)]
+pub enum Work {
+    /// Renders it to completion, as `render_pixel` does
+    Finish,
+    /// Adds one sample, unless it has converged
+    Refine,
 }
 
-fn ray_color(r: Ray, world: &HittableList, depth: u32) -> Vec3 {
-    if depth == 0 {
-        return Vec3::empty();
+/// Scene and settings shared by every pixel of a render.
+pub struct Renderer {
+    pub camera: Camera,
+    pub world: HittableList,
+    pub background: Background,
+    pub image_width: u32,
+    pub image_height: u32,
+    pub max_depth: u32,
+    /// Sample count of every pixel, or the most any pixel gets with
+    /// adaptive sampling.
+    pub samples_per_pixel: u32,
+    pub adaptive: Option<Adaptive>,
+    pub sampler: SamplerKind,
+    pub seed: u64,
+}
+
+impl Renderer {
+    /// Samples pixel `(x, y)` until it has `samples_per_pixel` samples or
+    /// adaptive sampling finds it converged.
+    pub fn render_pixel(&self, (x, y): (u32, u32)) -> PixelEstimate {
+        let mut estimate = PixelEstimate::default();
+        while estimate.samples() < self.samples_per_pixel {
+            estimate.add(self.sample_pixel((x, y), estimate.samples()));
+            if self.is_converged(&estimate) {
+                break;
+            }
+        }
+        estimate
     }
-    if let Some(rec) = world.hit(r, 0.001, f32::INFINITY)
-        && let Some(mat) = &rec.mat
-        && let Some(scatter_rec) = mat.scatter(r, &rec)
-    {
-        return scatter_rec.attenuation * ray_color(scatter_rec.scattered, world, depth - 1);
+
+    /// Renders the tiles of `framebuffer` in order on the thread pool,
+    /// calling `on_tile` with the index of each as it is written back, and
+    /// returns how many pixels were sampled. Workers wait while `control`
+    /// is paused and stop taking tiles once it is cancelled.
+    pub fn render_tiles(
+        &self,
+        framebuffer: &Framebuffer,
+        work: Work,
+        control: &Control,
+        on_tile: impl Fn(usize) + Sync,
+    ) -> usize {
+        let tiles = framebuffer.tiles();
+        // Rayon splits ranges from both ends, so workers claim tiles from
+        // a shared counter instead to keep them in order
+        let next = AtomicUsize::new(0);
+        (0..rayon::current_num_threads())
+            .into_par_iter()
+            .map(|_| {
+                let mut sampled = 0;
+                while control.proceed() {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&tile) = tiles.get(index) else {
+                        break;
+                    };
+                    let mut estimates = framebuffer.read(index);
+                    for ((x, row), estimate) in tile.pixels().zip(&mut estimates) {
+                        // Rows are stored top to bottom, which flips the
+                        // camera's y-axis
+                        let coords = (x, self.image_height - row - 1);
+                        match work {
+                            Work::Finish => *estimate = self.render_pixel(coords),
+                            Work::Refine if !self.is_converged(estimate) => {
+                                estimate.add(self.sample_pixel(coords, estimate.samples()))
+                            }
+                            Work::Refine => continue,
+                        }
+                        sampled += 1;
+                    }
+                    framebuffer.write(index, &estimates);
+                    on_tile(index);
+                }
+                sampled
+            })
+            .sum()
     }
-    let unit_direction = r.direction.unit();
-    let t = 0.5 * (unit_direction.y() + 1.0);
-    (1.0 - t) * Vec3::new(1.0, 1.0, 1.0) + t * Vec3::new(0.5, 0.7, 1.0)
+
+    pub fn is_converged(&self, estimate: &PixelEstimate) -> bool {
+        self.adaptive
+            .is_some_and(|adaptive| estimate.converged(&adaptive))
+    }
+
+    /// Sample number `sample` of pixel `(x, y)`. Each sample draws its
+    /// dimensions from its own sampler, so it is the same whichever order
+    /// samples are taken in.
+    pub fn sample_pixel(&self, (x, y): (u32, u32), sample: u32) -> Sample {
+        let mut sampler = Samplers::new(
+            self.sampler,
+            self.seed,
+            (x, y),
+            sample,
+            self.samples_per_pixel,
+        );
+        let (jitter_x, jitter_y) = sampler.next_2d();
+        let u = (x as f32 + jitter_x) / (self.image_width as f32 - 1.0);
+        let v = (y as f32 + jitter_y) / (self.image_height as f32 - 1.0);
+        let r = self.camera.get_ray(u, v, &mut sampler);
+        self.ray_color(r, &mut sampler)
+    }
+
+    /// Path traced radiance along `r`. Every non-specular vertex combines a
+    /// light sample and the scattered ray with multiple importance sampling,
+    /// weighting each by the power heuristic.
+    fn ray_color(&self, r: Ray, sampler: &mut Samplers) -> Sample {
+        let mut aovs = Aovs::default();
+        let mut throughput = Vec3::new(1.0, 1.0, 1.0);
+        let mut ray = r;
+        // MIS weight of emission found by the current ray
+        let mut emission_weight = 1.0;
+        // Non-specular vertices so far, which sort light into passes
+        let mut bounces = 0;
+        // Albedo and normal come from the first vertex that isn't a mirror
+        // or glass, as seen through them
+        let mut surface_found = false;
+
+        for vertex in 0..self.max_depth {
+            let Some(rec) = self.world.hit(ray, 0.001, f32::INFINITY, sampler) else {
+                let background = self.background.color(ray.direction);
+                aovs.add_light(bounces, emission_weight * throughput * background);
+                if !surface_found {
+                    aovs.albedo = throughput;
+                }
+                if vertex == 0 {
+                    aovs.depth = f32::INFINITY;
+                    aovs.position = Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+                }
+                break;
+            };
+            if vertex == 0 {
+                aovs.depth = self.camera.depth(rec.p);
+                aovs.position = rec.p;
+                aovs.object_id = rec.object;
+            }
+            let Some(mat) = &rec.mat else {
+                break;
+            };
+            aovs.add_light(bounces, emission_weight * throughput * mat.emitted(&rec));
+            let scatter_rec = mat.scatter(ray, &rec, sampler);
+            if !surface_found && scatter_rec.as_ref().is_none_or(|s| !s.is_specular) {
+                aovs.albedo = throughput * mat.albedo(&rec);
+                aovs.normal = rec.normal;
+                surface_found = true;
+            }
+            let Some(scatter_rec) = scatter_rec else {
+                break;
+            };
+            if scatter_rec.is_specular {
+                throughput = throughput * scatter_rec.attenuation;
+                ray = scatter_rec.scattered;
+                emission_weight = 1.0;
+                continue;
+            }
+            if scatter_rec.pdf <= 0.0 {
+                break;
+            }
+
+            bounces += 1;
+            let direct = self.direct_light(ray, &rec, mat, sampler);
+            aovs.add_light(bounces, throughput * direct);
+            throughput = throughput * scatter_rec.attenuation;
+            ray = scatter_rec.scattered;
+            let light_pdf = self.light_pdf(rec.p, ray.direction);
+            emission_weight = power_heuristic(scatter_rec.pdf, light_pdf);
+        }
+        Sample {
+            color: aovs.radiance(),
+            aovs,
+        }
+    }
+
+    /// Probability of sampling the background rather than the scene lights.
+    fn background_probability(&self) -> f32 {
+        match (self.background.is_sampled(), self.world.has_lights()) {
+            (false, _) => 0.0,
+            (true, false) => 1.0,
+            (true, true) => 0.5,
+        }
+    }
+
+    /// Density of `sample_light` picking `direction` from `origin`.
+    fn light_pdf(&self, origin: Vec3, direction: Vec3) -> f32 {
+        let p = self.background_probability();
+        let mut pdf = (1.0 - p) * self.world.light_pdf(origin, direction);
+        if p > 0.0 {
+            pdf += p * self.background.pdf_value(direction);
+        }
+        pdf
+    }
+
+    /// Direction from `origin` towards the scene lights or the background.
+    fn sample_light(&self, origin: Vec3, sampler: &mut Samplers) -> Vec3 {
+        if sampler.next_1d() < self.background_probability() {
+            self.background.sample(sampler)
+        } else {
+            self.world.sample_light(origin, sampler)
+        }
+    }
+
+    /// Light sampling estimate of the radiance reflected at `rec`, MIS
+    /// weighted against the BSDF. The radiance is taken from whatever the
+    /// shadow ray hits first, or the background if it escapes.
+    fn direct_light(
+        &self,
+        r_in: Ray,
+        rec: &HitRecord,
+        mat: &Materials,
+        sampler: &mut Samplers,
+    ) -> Vec3 {
+        if !self.world.has_lights() && !self.background.is_sampled() {
+            return Vec3::ZERO;
+        }
+        let direction = self.sample_light(rec.p, sampler);
+        let light_pdf = self.light_pdf(rec.p, direction);
+        if light_pdf <= 0.0 {
+            return Vec3::ZERO;
+        }
+        let f = mat.eval(r_in, rec, direction);
+        if f.near_zero() {
+            return Vec3::ZERO;
+        }
+        let shadow_ray = Ray::new(rec.p, direction, r_in.time);
+        let emitted = match self.world.hit(shadow_ray, 0.001, f32::INFINITY, sampler) {
+            Some(light_rec) => match &light_rec.mat {
+                Some(light_mat) => light_mat.emitted(&light_rec),
+                None => Vec3::ZERO,
+            },
+            None => self.background.color(direction),
+        };
+        let weight = power_heuristic(light_pdf, mat.scattering_pdf(r_in, rec, direction));
+        weight * f * emitted / light_pdf
+    }
+}
+
+/// Weight of a sample drawn with density `pdf` when `other_pdf` could have
+/// produced it too.
+#[inline]
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let a = pdf * pdf;
+    let b = other_pdf * other_pdf;
+    if a + b == 0.0 { 0.0 } else { a / (a + b) }
 }
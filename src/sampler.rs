@@ -0,0 +1,337 @@
+use crate::blue_noise::BlueNoise;
+use crate::halton::Halton;
+use crate::sobol::Sobol;
+
+/// Source of sample values, drawn one dimension at a time: the pixel
+/// position, the lens, the shutter time and then every bounce of the path.
+/// Low-discrepancy samplers spread each dimension evenly across the samples
+/// of a pixel.
+pub trait Sampler {
+    /// Next dimension of the current sample, in `[0, 1)`.
+    fn next_1d(&mut self) -> f32;
+
+    /// Next two dimensions, stratified jointly when the sampler supports it.
+    fn next_2d(&mut self) -> (f32, f32) {
+        (self.next_1d(), self.next_1d())
+    }
+
+    /// Next dimension mapped to `[min, max)`.
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_1d()
+    }
+}
+
+/// Sample pattern used for the pixels of a render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SamplerKind {
+    /// Independent uniform random numbers
+    Independent,
+    /// Jittered strata, shuffled independently in every dimension
+    Stratified,
+    /// Scrambled Halton sequence
+    Halton,
+    /// Owen-scrambled Sobol sequence
+    Sobol,
+    /// Blue noise across pixels, progressing with a rank-1 lattice across
+    /// samples
+    BlueNoise,
+}
+
+impl SamplerKind {
+    pub fn from_name(name: &str) -> Option<SamplerKind> {
+        <SamplerKind as clap::ValueEnum>::from_str(name, true).ok()
+    }
+}
+
+pub enum Samplers {
+    Independent(Pcg),
+    Stratified(Stratified),
+    Halton(Halton),
+    Sobol(Sobol),
+    BlueNoise(BlueNoise),
+}
+
+impl Samplers {
+    /// Sampler for sample `sample` of pixel `(x, y)`, out of
+    /// `samples_per_pixel`. Every pixel gets its own scrambling, so a render
+    /// is reproducible no matter how rayon schedules the pixels.
+    pub fn new(
+        kind: SamplerKind,
+        seed: u64,
+        (x, y): (u32, u32),
+        sample: u32,
+        samples_per_pixel: u32,
+    ) -> Samplers {
+        let pixel = ((y as u64) << 32) | x as u64;
+        let pixel_seed = splitmix64(seed ^ splitmix64(pixel));
+        let random = Pcg::new(splitmix64(pixel_seed ^ sample as u64));
+        let scramble = pixel_seed as u32;
+        match kind {
+            SamplerKind::Independent => Samplers::Independent(random),
+            SamplerKind::Stratified => Samplers::Stratified(Stratified {
+                seed: scramble,
+                sample,
+                count: samples_per_pixel.max(1),
+                dimension: 0,
+                random,
+            }),
+            SamplerKind::Halton => Samplers::Halton(Halton::new(scramble, sample, random)),
+            SamplerKind::Sobol => Samplers::Sobol(Sobol::new(scramble, sample)),
+            SamplerKind::BlueNoise => {
+                Samplers::BlueNoise(BlueNoise::new(seed as u32, (x, y), sample))
+            }
+        }
+    }
+}
+
+impl Sampler for Samplers {
+    #[inline]
+    fn next_1d(&mut self) -> f32 {
+        match self {
+            Samplers::Independent(s) => s.next_1d(),
+            Samplers::Stratified(s) => s.next_1d(),
+            Samplers::Halton(s) => s.next_1d(),
+            Samplers::Sobol(s) => s.next_1d(),
+            Samplers::BlueNoise(s) => s.next_1d(),
+        }
+    }
+
+    #[inline]
+    fn next_2d(&mut self) -> (f32, f32) {
+        match self {
+            Samplers::Independent(s) => s.next_2d(),
+            Samplers::Stratified(s) => s.next_2d(),
+            Samplers::Halton(s) => s.next_2d(),
+            Samplers::Sobol(s) => s.next_2d(),
+            Samplers::BlueNoise(s) => s.next_2d(),
+        }
+    }
+}
+
+/// Deterministic random number generator (PCG32).
+pub struct Pcg {
+    state: u64,
+    inc: u64,
+}
+
+impl Pcg {
+    pub fn new(seed: u64) -> Pcg {
+        let mut rng = Pcg {
+            state: 0,
+            inc: (splitmix64(seed ^ 0xda3e_39cb_94b9_5bdb) << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(splitmix64(seed));
+        rng.next_u32();
+        rng
+    }
+
+    #[inline]
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(self.inc);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+}
+
+impl Sampler for Pcg {
+    #[inline]
+    fn next_1d(&mut self) -> f32 {
+        to_unit_float(self.next_u32())
+    }
+}
+
+/// Jittered sampling: each dimension is split into as many strata as there
+/// are samples per pixel, and every sample lands in a different one. The
+/// order of the strata is shuffled per pixel and dimension.
+pub struct Stratified {
+    seed: u32,
+    sample: u32,
+    count: u32,
+    dimension: u32,
+    random: Pcg,
+}
+
+impl Stratified {
+    fn dimension_seed(&mut self) -> u32 {
+        let seed = hash_combine(self.seed, self.dimension);
+        self.dimension += 1;
+        seed
+    }
+}
+
+impl Sampler for Stratified {
+    fn next_1d(&mut self) -> f32 {
+        let seed = self.dimension_seed();
+        let stratum = permute(self.sample % self.count, self.count, seed);
+        below_one((stratum as f32 + self.random.next_1d()) / self.count as f32)
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        let seed = self.dimension_seed();
+        self.dimension += 1;
+        let columns = (self.count as f32).sqrt().ceil() as u32;
+        let rows = self.count.div_ceil(columns);
+        let cell = permute(self.sample % self.count, columns * rows, seed);
+        let (jitter_x, jitter_y) = self.random.next_2d();
+        (
+            below_one(((cell % columns) as f32 + jitter_x) / columns as f32),
+            below_one(((cell / columns) as f32 + jitter_y) / rows as f32),
+        )
+    }
+}
+
+/// Keeps a jittered stratum position, which can round up to 1 in the last
+/// stratum, inside `[0, 1)`.
+#[inline]
+fn below_one(x: f32) -> f32 {
+    x.min(1.0 - f32::EPSILON / 2.0)
+}
+
+/// Maps 32 random bits to a float in `[0, 1)`.
+#[inline]
+pub fn to_unit_float(bits: u32) -> f32 {
+    (bits >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+}
+
+#[inline]
+pub fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Mixes `value` into `seed`, for deriving independent seeds per dimension.
+#[inline]
+pub fn hash_combine(seed: u32, value: u32) -> u32 {
+    let h = seed
+        ^ value
+            .wrapping_add(0x9e37_79b9)
+            .wrapping_add(seed << 6)
+            .wrapping_add(seed >> 2);
+    // Final avalanche of MurmurHash3
+    let h = (h ^ (h >> 16)).wrapping_mul(0x85eb_ca6b);
+    let h = (h ^ (h >> 13)).wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+/// Pseudorandom permutation of `0..n` selected by `seed`, evaluated at `i`
+/// (Kensler, "Correlated Multi-Jittered Sampling").
+pub fn permute(i: u32, n: u32, seed: u32) -> u32 {
+    let mut w = n.wrapping_sub(1);
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    let p = seed;
+    let mut i = i;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170_893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929_eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935_fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dc_b303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e50_1cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860_a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < n {
+            break;
+        }
+    }
+    ((i as u64 + p as u64) % n as u64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permute_is_a_bijection() {
+        for n in [1, 2, 3, 7, 16, 100, 257] {
+            for seed in [0, 1, 0xdead_beef] {
+                let mut seen = vec![false; n as usize];
+                for i in 0..n {
+                    let j = permute(i, n, seed);
+                    assert!(j < n && !seen[j as usize], "n {} seed {}", n, seed);
+                    seen[j as usize] = true;
+                }
+            }
+        }
+    }
+
+    /// Checks that the first `dimensions` dimensions of `count` samples of
+    /// a pixel each put one value in every one of `count` strata.
+    fn assert_stratified(kind: SamplerKind, count: u32, dimensions: usize) {
+        for pixel in [(0, 0), (5, 9), (123, 45)] {
+            let mut strata = vec![vec![false; count as usize]; dimensions];
+            for sample in 0..count {
+                let mut sampler = Samplers::new(kind, 42, pixel, sample, count);
+                for seen in &mut strata {
+                    let stratum = (sampler.next_1d() * count as f32) as usize;
+                    assert!(!seen[stratum], "{:?} pixel {:?}", kind, pixel);
+                    seen[stratum] = true;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stratified_covers_every_stratum() {
+        assert_stratified(SamplerKind::Stratified, 16, 8);
+        assert_stratified(SamplerKind::Stratified, 13, 8);
+    }
+
+    #[test]
+    fn sobol_covers_every_stratum() {
+        assert_stratified(SamplerKind::Sobol, 16, 8);
+        assert_stratified(SamplerKind::Sobol, 64, 8);
+    }
+
+    #[test]
+    fn halton_covers_every_stratum() {
+        // Only the first dimension uses base 2, and N = 2^k strata
+        assert_stratified(SamplerKind::Halton, 16, 1);
+        assert_stratified(SamplerKind::Halton, 64, 1);
+    }
+
+    #[test]
+    fn samples_stay_in_unit_interval() {
+        let kinds = [
+            SamplerKind::Independent,
+            SamplerKind::Stratified,
+            SamplerKind::Halton,
+            SamplerKind::Sobol,
+            SamplerKind::BlueNoise,
+        ];
+        for kind in kinds {
+            for count in [1, 3, 7, 64] {
+                for sample in 0..count {
+                    let mut sampler = Samplers::new(kind, 7, (3, 11), sample, count);
+                    for _ in 0..32 {
+                        let v = sampler.next_1d();
+                        let (a, b) = sampler.next_2d();
+                        for v in [v, a, b] {
+                            assert!((0.0..1.0).contains(&v), "{:?} gave {}", kind, v);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
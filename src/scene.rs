@@ -1,42 +1,126 @@
+use crate::adaptive::Adaptive;
+use crate::background::Background;
+use crate::camera::CameraSettings;
+use crate::cuboid::Cuboid;
 use crate::hittable::HittableList;
 use crate::material::Materials;
+use crate::plane::Plane;
+use crate::quad::Quad;
+use crate::sampler::{Pcg, Sampler, SamplerKind};
 use crate::sphere::Sphere;
+use crate::texture::Textures;
+use crate::tonemap::DisplayTransform;
 use crate::vec3::Vec3;
 
-use rand::{Rng, random};
+/// Output resolution and sampling parameters.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageSettings {
+    pub width: u32,
+    pub aspect_ratio: f32,
+    pub samples_per_pixel: u32,
+    pub max_depth: u32,
+    /// Relative error at which adaptive sampling stops a pixel, 0 to always
+    /// take `samples_per_pixel`.
+    pub adaptive_threshold: f32,
+    pub min_samples: u32,
+    pub sampler: SamplerKind,
+    pub display: DisplayTransform,
+}
+
+impl Default for ImageSettings {
+    fn default() -> ImageSettings {
+        ImageSettings {
+            width: 1200,
+            aspect_ratio: 3.0 / 2.0,
+            samples_per_pixel: 100,
+            max_depth: 10,
+            adaptive_threshold: 0.0,
+            min_samples: 16,
+            sampler: SamplerKind::Independent,
+            display: DisplayTransform::default(),
+        }
+    }
+}
 
-pub fn random_scene() -> HittableList {
+impl ImageSettings {
+    /// At least 2, like the width, since rows are mapped to the viewport
+    /// as y / (height - 1).
+    pub fn height(&self) -> u32 {
+        ((self.width as f32 / self.aspect_ratio) as u32).max(2)
+    }
+
+    pub fn adaptive(&self) -> Option<Adaptive> {
+        (self.adaptive_threshold > 0.0).then_some(Adaptive {
+            threshold: self.adaptive_threshold,
+            min_samples: self.min_samples,
+        })
+    }
+}
+
+pub struct Scene {
+    pub world: HittableList,
+    pub camera: CameraSettings,
+    pub image: ImageSettings,
+    pub background: Background,
+}
+
+/// Looks up one of the scenes built into the renderer by name.
+pub fn builtin_scene(name: &str, seed: u64) -> Option<Scene> {
+    match name {
+        "random" => Some(default_scene(seed)),
+        "cornell" => Some(cornell_box()),
+        _ => None,
+    }
+}
+
+pub fn default_scene(seed: u64) -> Scene {
+    let mut world = random_scene(seed);
+    world.build_bvh();
+    Scene {
+        world,
+        camera: CameraSettings::default(),
+        image: ImageSettings::default(),
+        background: Background::Sky,
+    }
+}
+
+pub fn random_scene(seed: u64) -> HittableList {
     let mut world = HittableList::new();
 
     let material_ground = Materials::Lambertian {
-        albedo: Vec3::new(0.5, 0.5, 0.5),
+        albedo: Textures::Solid(Vec3::new(0.5, 0.5, 0.5)),
     };
-    world.add_sphere(Sphere::new(
-        Vec3::new(0.0, -1000.0, 0.0),
-        1000.0,
+    world.add_plane(Plane::new(
+        Vec3::ZERO,
+        Vec3::new(0.0, 1.0, 0.0),
         material_ground,
     ));
 
-    let mut rng = rand::rng();
+    let mut sampler = Pcg::new(seed);
 
     for a in -11..11 {
         for b in -11..11 {
-            let choose_mat: f32 = random();
-            let rng_x: f32 = random();
-            let rng_z: f32 = random();
+            let choose_mat: f32 = sampler.next_1d();
+            let rng_x: f32 = sampler.next_1d();
+            let rng_z: f32 = sampler.next_1d();
             let center: Vec3 = Vec3::new(a as f32 + 0.9 * rng_x, 0.2, b as f32 + 0.9 * rng_z);
 
             if (center - Vec3::new(4.0, 0.2, 0.0)).length() > 0.9 {
                 if choose_mat < 0.8 {
                     // difuse
-                    let albedo = Vec3::random() * Vec3::random();
-                    let sphere_material = Materials::Lambertian { albedo };
+                    let albedo = Vec3::random(&mut sampler) * Vec3::random(&mut sampler);
+                    let sphere_material = Materials::Lambertian {
+                        albedo: Textures::Solid(albedo),
+                    };
                     world.add_sphere(Sphere::new(center, 0.2, sphere_material));
                 } else if choose_mat < 0.95 {
                     // metal
-                    let albedo = Vec3::random_range(0.5, 1.0);
-                    let fuzz = rng.random_range(0.0..0.5);
-                    let sphere_material = Materials::Metal { albedo, fuzz };
+                    let albedo = Vec3::random_range(&mut sampler, 0.5, 1.0);
+                    let fuzz = sampler.range(0.0, 0.5);
+                    let sphere_material = Materials::Metal {
+                        albedo: Textures::Solid(albedo),
+                        fuzz,
+                    };
                     world.add_sphere(Sphere::new(center, 0.2, sphere_material));
                 } else {
                     // glass
@@ -51,15 +135,82 @@ pub fn random_scene() -> HittableList {
     world.add_sphere(Sphere::new(Vec3::new(0.0, 1.0, 0.0), 1.0, material1));
 
     let material2 = Materials::Lambertian {
-        albedo: Vec3::new(0.4, 0.2, 0.1),
+        albedo: Textures::Solid(Vec3::new(0.4, 0.2, 0.1)),
     };
     world.add_sphere(Sphere::new(Vec3::new(-4.0, 1.0, 0.0), 1.0, material2));
 
     let material3 = Materials::Metal {
-        albedo: Vec3::new(0.7, 0.6, 0.5),
+        albedo: Textures::Solid(Vec3::new(0.7, 0.6, 0.5)),
         fuzz: 0.0,
     };
     world.add_sphere(Sphere::new(Vec3::new(4.0, 1.0, 0.0), 1.0, material3));
 
     world
 }
+
+pub fn cornell_box() -> Scene {
+    let mut world = HittableList::new();
+
+    let red = Materials::Lambertian {
+        albedo: Textures::Solid(Vec3::new(0.65, 0.05, 0.05)),
+    };
+    let white = Materials::Lambertian {
+        albedo: Textures::Solid(Vec3::new(0.73, 0.73, 0.73)),
+    };
+    let green = Materials::Lambertian {
+        albedo: Textures::Solid(Vec3::new(0.12, 0.45, 0.15)),
+    };
+    let light = Materials::DiffuseLight {
+        emit: Vec3::new(15.0, 15.0, 15.0),
+    };
+
+    let x = Vec3::new(555.0, 0.0, 0.0);
+    let y = Vec3::new(0.0, 555.0, 0.0);
+    let z = Vec3::new(0.0, 0.0, 555.0);
+    world.add_quad(Quad::new(x, y, z, green));
+    world.add_quad(Quad::new(Vec3::ZERO, z, y, red));
+    world.add_quad(Quad::new(Vec3::ZERO, x, z, white.clone()));
+    world.add_quad(Quad::new(x + y + z, -x, -z, white.clone()));
+    world.add_quad(Quad::new(z, y, x, white.clone()));
+    world.add_quad(Quad::new(
+        Vec3::new(343.0, 554.0, 332.0),
+        Vec3::new(-130.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -105.0),
+        light,
+    ));
+
+    world.add_box(Cuboid::oriented(
+        Vec3::new(347.5, 165.0, 377.5),
+        Vec3::new(165.0, 330.0, 165.0),
+        Vec3::new(0.0, 15.0, 0.0),
+        white.clone(),
+    ));
+    world.add_box(Cuboid::oriented(
+        Vec3::new(212.5, 82.5, 147.5),
+        Vec3::new(165.0, 165.0, 165.0),
+        Vec3::new(0.0, -18.0, 0.0),
+        white,
+    ));
+    world.build_bvh();
+
+    Scene {
+        world,
+        camera: CameraSettings {
+            lookfrom: Vec3::new(278.0, 278.0, -800.0),
+            lookat: Vec3::new(278.0, 278.0, 0.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: 40.0,
+            aperture: 0.0,
+            focus_dist: 10.0,
+            ..CameraSettings::default()
+        },
+        image: ImageSettings {
+            width: 600,
+            aspect_ratio: 1.0,
+            samples_per_pixel: 200,
+            max_depth: 50,
+            ..ImageSettings::default()
+        },
+        background: Background::Solid(Vec3::ZERO),
+    }
+}
@@ -0,0 +1,75 @@
+use crate::sampler::{Sampler, hash_combine, to_unit_float};
+
+/// Owen-scrambled Sobol points, padded two dimensions at a time: every pair
+/// of dimensions uses the first two Sobol dimensions with its own
+/// scrambling and sample order (Burley, "Practical Hash-based Owen
+/// Scrambling").
+pub struct Sobol {
+    seed: u32,
+    index: u32,
+    dimension: u32,
+}
+
+impl Sobol {
+    pub fn new(seed: u32, index: u32) -> Sobol {
+        Sobol {
+            seed,
+            index,
+            dimension: 0,
+        }
+    }
+
+    /// Shuffled sample index and seed of the next dimension pair.
+    fn next_pair(&mut self) -> (u32, u32) {
+        let seed = hash_combine(self.seed, self.dimension);
+        self.dimension += 1;
+        (nested_uniform_scramble(self.index, seed), seed)
+    }
+}
+
+impl Sampler for Sobol {
+    fn next_1d(&mut self) -> f32 {
+        let (index, seed) = self.next_pair();
+        let x = nested_uniform_scramble(index.reverse_bits(), hash_combine(seed, 0));
+        to_unit_float(x)
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        let (index, seed) = self.next_pair();
+        let x = nested_uniform_scramble(index.reverse_bits(), hash_combine(seed, 0));
+        let y = nested_uniform_scramble(sobol_second_dimension(index), hash_combine(seed, 1));
+        (to_unit_float(x), to_unit_float(y))
+    }
+}
+
+/// Second dimension of the Sobol sequence, whose generator matrix is
+/// Pascal's triangle mod 2. The first is the bit reversed index.
+fn sobol_second_dimension(index: u32) -> u32 {
+    let mut result = 0;
+    let mut direction = 1 << 31;
+    let mut bits = index;
+    while bits != 0 {
+        if bits & 1 != 0 {
+            result ^= direction;
+        }
+        bits >>= 1;
+        direction ^= direction >> 1;
+    }
+    result
+}
+
+/// Owen scrambling of the bits of `x`, read from the most significant.
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+/// Hash where every bit only depends on the bits below it, so it permutes
+/// the bits of a reversed number like a nested scramble would.
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x
+}
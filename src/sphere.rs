@@ -1,11 +1,13 @@
+use crate::aabb::Aabb;
 use crate::hittable::{HitRecord, Hittable};
 use crate::material::Materials;
+use crate::onb::Onb;
 use crate::ray::Ray;
+use crate::sampler::{Sampler, Samplers};
 use crate::vec3::Vec3;
 
 pub struct Sphere {
     center: Vec3,
-    #[allow(dead_code)]
     radius: f32,
     radius_squared: f32,
     inv_radius: f32,
@@ -22,10 +24,13 @@ impl Sphere {
             mat: m,
         }
     }
-}
 
-impl Hittable for Sphere {
-    fn hit(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+    pub fn material(&self) -> &Materials {
+        &self.mat
+    }
+
+    /// Nearest `t` in `[t_min, t_max]` where `r` crosses the surface.
+    fn intersect(&self, r: Ray, t_min: f32, t_max: f32) -> Option<f32> {
         let oc = r.origin - self.center;
         let a = r.direction.length_squared();
         let half_b = Vec3::dot(oc, r.direction);
@@ -46,21 +51,74 @@ impl Hittable for Sphere {
                 return None;
             }
         }
-        let t = root;
+        Some(root)
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut Samplers,
+    ) -> Option<HitRecord<'_>> {
+        let t = self.intersect(r, t_min, t_max)?;
         let p = r.at(t);
         let outward_normal = (p - self.center) * self.inv_radius; // Use pre-computed inverse radius
-        let front_face = Vec3::dot(r.direction, outward_normal) < 0.0;
-        let normal = if front_face {
-            outward_normal
-        } else {
-            -outward_normal
-        };
-        Some(HitRecord {
-            p,
-            normal,
-            mat: Some(&self.mat),
+        Some(HitRecord::new(
+            r,
             t,
-            front_face,
-        })
+            outward_normal,
+            sphere_uv(outward_normal),
+            &self.mat,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        Aabb::new(self.center - r, self.center + r)
     }
+
+    // Directions are sampled uniformly inside the cone the sphere subtends,
+    // which is not defined from inside the sphere
+    fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f32 {
+        let distance_squared = (self.center - origin).length_squared();
+        if distance_squared <= self.radius_squared
+            || self
+                .intersect(Ray::new(origin, direction, 0.0), 0.001, f32::INFINITY)
+                .is_none()
+        {
+            return 0.0;
+        }
+        let cos_theta_max = (1.0 - self.radius_squared / distance_squared).sqrt();
+        let solid_angle = 2.0 * std::f32::consts::PI * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut Samplers) -> Vec3 {
+        let direction = self.center - origin;
+        let distance_squared = direction.length_squared();
+        if distance_squared <= self.radius_squared {
+            return Vec3::random_unit_vector(sampler);
+        }
+        let (r1, r2) = sampler.next_2d();
+        let cos_theta_max = (1.0 - self.radius_squared / distance_squared).sqrt();
+        let z = 1.0 + r2 * (cos_theta_max - 1.0);
+        let phi = 2.0 * std::f32::consts::PI * r1;
+        let sin_theta = (1.0 - z * z).max(0.0).sqrt();
+        let local = Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z);
+        Onb::new(direction).local(local)
+    }
+}
+
+/// Maps a point on the unit sphere to texture coordinates: `u` is the angle
+/// around the Y axis starting from -X, `v` the angle from -Y to +Y.
+fn sphere_uv(p: Vec3) -> (f32, f32) {
+    let theta = (-p.y()).clamp(-1.0, 1.0).acos();
+    let phi = (-p.z()).atan2(p.x()) + std::f32::consts::PI;
+    (
+        phi / (2.0 * std::f32::consts::PI),
+        theta / std::f32::consts::PI,
+    )
 }
@@ -0,0 +1,165 @@
+use crate::perlin::Perlin;
+use crate::vec3::Vec3;
+
+use anyhow::Context;
+use std::path::Path;
+use std::sync::Arc;
+
+pub trait Texture {
+    /// Color at surface coordinates `(u, v)` and world position `p`.
+    fn value(&self, u: f32, v: f32, p: Vec3) -> Vec3;
+}
+
+#[derive(Clone)]
+pub enum Textures {
+    Solid(Vec3),
+    /// 3D checker pattern alternating between two textures every `1 / inv_scale`
+    /// units.
+    Checker {
+        inv_scale: f32,
+        even: Arc<Textures>,
+        odd: Arc<Textures>,
+    },
+    Image(Arc<ImageTexture>),
+    Noise {
+        perlin: Arc<Perlin>,
+        scale: f32,
+        kind: NoiseKind,
+    },
+}
+
+impl Texture for Textures {
+    fn value(&self, u: f32, v: f32, p: Vec3) -> Vec3 {
+        match self {
+            Textures::Solid(color) => *color,
+            Textures::Checker {
+                inv_scale,
+                even,
+                odd,
+            } => {
+                let cell = (inv_scale * p.x()).floor() as i32
+                    + (inv_scale * p.y()).floor() as i32
+                    + (inv_scale * p.z()).floor() as i32;
+                if cell % 2 == 0 {
+                    even.value(u, v, p)
+                } else {
+                    odd.value(u, v, p)
+                }
+            }
+            Textures::Image(image) => image.value(u, v, p),
+            Textures::Noise {
+                perlin,
+                scale,
+                kind,
+            } => {
+                let intensity = match kind {
+                    NoiseKind::Perlin => 0.5 * (1.0 + perlin.noise(*scale * p)),
+                    NoiseKind::Turbulence => perlin.turbulence(*scale * p, 7),
+                    NoiseKind::Marble => {
+                        0.5 * (1.0 + (scale * p.z() + 10.0 * perlin.turbulence(p, 7)).sin())
+                    }
+                };
+                Vec3::new(intensity, intensity, intensity)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NoiseKind {
+    Perlin,
+    /// Several octaves of noise summed together.
+    Turbulence,
+    /// Sine stripes along z, phase shifted by turbulence.
+    Marble,
+}
+
+/// How texture coordinates outside `[0, 1]` are mapped back onto the image.
+#[derive(Debug, Clone, Copy)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl WrapMode {
+    fn wrap(self, i: i64, size: i64) -> usize {
+        let wrapped = match self {
+            WrapMode::Repeat => i.rem_euclid(size),
+            WrapMode::Clamp => i.clamp(0, size - 1),
+            WrapMode::Mirror => {
+                let period = i.rem_euclid(2 * size);
+                if period < size {
+                    period
+                } else {
+                    2 * size - 1 - period
+                }
+            }
+        };
+        wrapped as usize
+    }
+}
+
+/// Image sampled with bilinear filtering. Pixels are stored as linear
+/// colors, decoded from sRGB on load.
+pub struct ImageTexture {
+    width: u32,
+    height: u32,
+    pixels: Vec<Vec3>,
+    wrap: WrapMode,
+}
+
+impl ImageTexture {
+    pub fn load(path: &Path, wrap: WrapMode) -> anyhow::Result<ImageTexture> {
+        let image = image::open(path)
+            .with_context(|| format!("Failed to load texture {}", path.display()))?
+            .into_rgb8();
+        let pixels = image
+            .pixels()
+            .map(|p| {
+                Vec3::new(
+                    srgb_to_linear(p[0]),
+                    srgb_to_linear(p[1]),
+                    srgb_to_linear(p[2]),
+                )
+            })
+            .collect();
+        Ok(ImageTexture {
+            width: image.width(),
+            height: image.height(),
+            pixels,
+            wrap,
+        })
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vec3 {
+        let x = self.wrap.wrap(x, self.width as i64);
+        let y = self.wrap.wrap(y, self.height as i64);
+        self.pixels[y * self.width as usize + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f32, v: f32, _p: Vec3) -> Vec3 {
+        // Image rows go top to bottom while v goes up; texel centers sit at
+        // half-integer coordinates
+        let x = u * self.width as f32 - 0.5;
+        let y = (1.0 - v) * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = (1.0 - tx) * self.texel(x0, y0) + tx * self.texel(x0 + 1, y0);
+        let bottom = (1.0 - tx) * self.texel(x0, y0 + 1) + tx * self.texel(x0 + 1, y0 + 1);
+        (1.0 - ty) * top + ty * bottom
+    }
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let c = value as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
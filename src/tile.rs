@@ -0,0 +1,209 @@
+/// Order tiles are rendered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TileOrder {
+    /// Outwards from the center of the image, where the subject usually is
+    Spiral,
+    /// Along a Hilbert curve, so each tile is next to the one before
+    Hilbert,
+}
+
+/// Rectangle of pixels rendered as one piece of work, in image coordinates
+/// with rows from the top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    /// Pixel coordinates, row by row.
+    pub fn pixels(self) -> impl Iterator<Item = (u32, u32)> {
+        let Tile {
+            x,
+            y,
+            width,
+            height,
+        } = self;
+        (y..y + height).flat_map(move |row| (x..x + width).map(move |column| (column, row)))
+    }
+
+    pub fn area(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+
+    /// Smallest tile covering both.
+    pub fn union(self, other: Tile) -> Tile {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Tile {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+}
+
+/// How an image is split into tiles.
+#[derive(Debug, Clone, Copy)]
+pub struct Tiling {
+    /// Width and height of the tiles, except at the right and bottom edges.
+    pub size: u32,
+    pub order: TileOrder,
+}
+
+impl Tiling {
+    /// Tiles covering a `width` by `height` image, in rendering order.
+    pub fn tiles(&self, width: u32, height: u32) -> Vec<Tile> {
+        let columns = width.div_ceil(self.size);
+        let rows = height.div_ceil(self.size);
+        let cells = match self.order {
+            TileOrder::Spiral => spiral(columns, rows),
+            TileOrder::Hilbert => hilbert(columns, rows),
+        };
+        cells
+            .into_iter()
+            .map(|(column, row)| {
+                let (x, y) = (column * self.size, row * self.size);
+                Tile {
+                    x,
+                    y,
+                    width: self.size.min(width - x),
+                    height: self.size.min(height - y),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Cells of a `columns` by `rows` grid, walking a square spiral out from
+/// the middle and skipping the parts of it outside the grid.
+fn spiral(columns: u32, rows: u32) -> Vec<(u32, u32)> {
+    const DIRECTIONS: [(i64, i64); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let total = (columns * rows) as usize;
+    let mut cells = Vec::with_capacity(total);
+    let (mut x, mut y) = (((columns - 1) / 2) as i64, ((rows - 1) / 2) as i64);
+    let mut leg = 0;
+    while cells.len() < total {
+        // Legs grow by one every second turn: 1, 1, 2, 2, 3, 3, ...
+        let (dx, dy) = DIRECTIONS[leg % 4];
+        for _ in 0..leg / 2 + 1 {
+            if (0..columns as i64).contains(&x) && (0..rows as i64).contains(&y) {
+                cells.push((x as u32, y as u32));
+            }
+            x += dx;
+            y += dy;
+        }
+        leg += 1;
+    }
+    cells
+}
+
+/// Cells of a `columns` by `rows` grid along Hilbert curves through a row
+/// of power of two squares as large as the short side of the grid needs,
+/// skipping the parts of them outside the grid. Each curve ends next to
+/// where the following one starts.
+fn hilbert(columns: u32, rows: u32) -> Vec<(u32, u32)> {
+    let (long, short) = (columns.max(rows) as u64, columns.min(rows) as u64);
+    let side = short.next_power_of_two();
+    let mut cells = Vec::with_capacity((columns * rows) as usize);
+    for square in 0..long.div_ceil(side) {
+        for d in 0..side * side {
+            let (along, across) = hilbert_cell(side, d);
+            let along = square * side + along;
+            // The curve runs along the first coordinate, so swap them when
+            // the squares are stacked vertically
+            let (x, y) = if columns >= rows {
+                (along, across)
+            } else {
+                (across, along)
+            };
+            if x < columns as u64 && y < rows as u64 {
+                cells.push((x as u32, y as u32));
+            }
+        }
+    }
+    cells
+}
+
+/// Cell at distance `d` along the Hilbert curve through a `side` by `side`
+/// grid, which starts at `(0, 0)` and ends at `(side - 1, 0)`.
+fn hilbert_cell(side: u64, d: u64) -> (u64, u64) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < side {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_covers(width: u32, height: u32, tiling: Tiling) {
+        let mut covered = vec![0; (width * height) as usize];
+        for tile in tiling.tiles(width, height) {
+            assert!(tile.width > 0 && tile.height > 0);
+            assert!(tile.width <= tiling.size && tile.height <= tiling.size);
+            for (x, y) in tile.pixels() {
+                covered[(y * width + x) as usize] += 1;
+            }
+        }
+        assert!(
+            covered.iter().all(|&count| count == 1),
+            "{}x{} {:?}",
+            width,
+            height,
+            tiling
+        );
+    }
+
+    #[test]
+    fn tiles_cover_every_pixel_once() {
+        for order in [TileOrder::Spiral, TileOrder::Hilbert] {
+            for size in [1, 7, 64] {
+                for (width, height) in [(37, 23), (23, 37), (64, 64), (1000, 3), (2, 300)] {
+                    assert_covers(width, height, Tiling { size, order });
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hilbert_steps_between_neighbours() {
+        for (columns, rows) in [(8, 8), (32, 8), (4, 16)] {
+            let cells = hilbert(columns, rows);
+            assert_eq!(cells.len(), (columns * rows) as usize);
+            for pair in cells.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                assert_eq!(a.0.abs_diff(b.0) + a.1.abs_diff(b.1), 1, "{:?}", pair);
+            }
+        }
+    }
+
+    #[test]
+    fn spiral_starts_in_the_middle() {
+        let tiles = Tiling {
+            size: 10,
+            order: TileOrder::Spiral,
+        }
+        .tiles(50, 30);
+        assert_eq!((tiles[0].x, tiles[0].y), (20, 10));
+    }
+}
@@ -0,0 +1,132 @@
+use crate::vec3::Vec3;
+
+/// Curve compressing linear radiance into the displayable range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ToneMapper {
+    /// Clip everything above 1
+    Clamp,
+    /// x / (1 + x), never quite reaching white
+    Reinhard,
+    /// Reinhard reaching white at the white point
+    ExtendedReinhard,
+    /// Filmic fit of the ACES reference and sRGB output transforms
+    Aces,
+    /// Filmic curve that desaturates highlights instead of skewing their hue
+    Agx,
+}
+
+impl ToneMapper {
+    pub fn from_name(name: &str) -> Option<ToneMapper> {
+        <ToneMapper as clap::ValueEnum>::from_str(name, true).ok()
+    }
+
+    /// Maps linear radiance to linear display values in `[0, 1]`.
+    fn apply(self, color: Vec3, white: f32) -> Vec3 {
+        match self {
+            ToneMapper::Clamp => color,
+            ToneMapper::Reinhard => color.map(|c| c / (1.0 + c)),
+            ToneMapper::ExtendedReinhard => {
+                let white_sq = white * white;
+                color.map(|c| c * (1.0 + c / white_sq) / (1.0 + c))
+            }
+            ToneMapper::Aces => aces(color),
+            ToneMapper::Agx => agx(color),
+        }
+        .map(|c| c.clamp(0.0, 1.0))
+    }
+}
+
+/// Transform from the linear radiance of a render to the sRGB values
+/// written to 8-bit images and shown in the window.
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayTransform {
+    /// Exposure adjustment in stops, applied before tone mapping.
+    pub exposure: f32,
+    pub tone_mapper: ToneMapper,
+    /// Radiance that extended Reinhard maps to white.
+    pub white: f32,
+}
+
+impl Default for DisplayTransform {
+    fn default() -> DisplayTransform {
+        DisplayTransform {
+            exposure: 0.0,
+            tone_mapper: ToneMapper::Clamp,
+            white: 4.0,
+        }
+    }
+}
+
+impl DisplayTransform {
+    pub fn apply(&self, color: Vec3) -> Vec3 {
+        let exposed = color * self.exposure.exp2();
+        self.tone_mapper.apply(exposed, self.white).map(srgb_encode)
+    }
+}
+
+/// sRGB transfer function, from linear to encoded values.
+pub fn srgb_encode(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn mul(m: &[[f32; 3]; 3], v: Vec3) -> Vec3 {
+    Vec3::new(
+        m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+        m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+        m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+    )
+}
+
+/// Stephen Hill's fit of the ACES RRT and sRGB ODT, taking and returning
+/// linear sRGB.
+fn aces(color: Vec3) -> Vec3 {
+    const INPUT: [[f32; 3]; 3] = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+    const OUTPUT: [[f32; 3]; 3] = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ];
+    let v = mul(&INPUT, color);
+    let fitted = v.map(|c| {
+        let a = c * (c + 0.024_578_6) - 0.000_090_537;
+        let b = c * (0.983_729 * c + 0.432_951) + 0.238_081;
+        a / b
+    });
+    mul(&OUTPUT, fitted)
+}
+
+/// AgX with the default look: an inset into a wider gamut, a log encoding
+/// and a polynomial fit of the sigmoid, then back to linear sRGB
+/// (Wrensch, "Minimal AgX Implementation").
+fn agx(color: Vec3) -> Vec3 {
+    const INSET: [[f32; 3]; 3] = [
+        [0.842_479_06, 0.078_433_6, 0.079_223_745],
+        [0.042_328_242, 0.878_468_6, 0.079_166_13],
+        [0.042_375_655, 0.078_433_6, 0.879_143],
+    ];
+    const OUTSET: [[f32; 3]; 3] = [
+        [1.196_879, -0.098_020_88, -0.099_029_74],
+        [-0.052_896_85, 1.151_903_1, -0.098_961_18],
+        [-0.052_971_635, -0.098_043_45, 1.151_073_7],
+    ];
+    const MIN_EV: f32 = -12.473_93;
+    const MAX_EV: f32 = 4.026_069;
+    let encoded = mul(&INSET, color).map(|c| {
+        let x = (c.max(1e-10).log2().clamp(MIN_EV, MAX_EV) - MIN_EV) / (MAX_EV - MIN_EV);
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232
+    });
+    // The curve produces display-encoded values; undo the 2.2 gamma it
+    // assumes so the sRGB encoding can be applied like for the others
+    mul(&OUTSET, encoded).map(|c| c.max(0.0).powf(2.2))
+}
@@ -0,0 +1,136 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Materials;
+use crate::quad;
+use crate::ray::Ray;
+use crate::sampler::{Sampler, Samplers};
+use crate::vec3::Vec3;
+
+pub struct Triangle {
+    vertices: [Vec3; 3],
+    /// Per-vertex shading normals, interpolated across the face.
+    normals: Option<[Vec3; 3]>,
+    uvs: [(f32, f32); 3],
+    mat: Materials,
+}
+
+impl Triangle {
+    pub fn new(a: Vec3, b: Vec3, c: Vec3, m: Materials) -> Triangle {
+        Triangle {
+            vertices: [a, b, c],
+            normals: None,
+            uvs: [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)],
+            mat: m,
+        }
+    }
+
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Triangle {
+        self.normals = Some(normals.map(Vec3::unit));
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: [(f32, f32); 3]) -> Triangle {
+        self.uvs = uvs;
+        self
+    }
+
+    pub fn material(&self) -> &Materials {
+        &self.mat
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut Samplers,
+    ) -> Option<HitRecord<'_>> {
+        let (t, [b0, b1, b2]) = intersect(self.vertices, r, t_min, t_max)?;
+        let [uv0, uv1, uv2] = self.uvs;
+        let uv = (
+            b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0,
+            b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1,
+        );
+        let [a, b, c] = self.vertices;
+        let geometric_normal = Vec3::cross(b - a, c - a).unit();
+        let mut rec = HitRecord::new(r, t, geometric_normal, uv, &self.mat);
+        if let Some([n0, n1, n2]) = self.normals {
+            set_shading_normal(&mut rec, geometric_normal, b0 * n0 + b1 * n1 + b2 * n2);
+        }
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let [a, b, c] = self.vertices;
+        Aabb::new(a, b).grow(c).pad(1e-4)
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f32 {
+        let Some((t, _)) = intersect(
+            self.vertices,
+            Ray::new(origin, direction, 0.0),
+            0.001,
+            f32::INFINITY,
+        ) else {
+            return 0.0;
+        };
+        let [a, b, c] = self.vertices;
+        let n = Vec3::cross(b - a, c - a);
+        quad::area_pdf(0.5 * n.length(), t, direction, n.unit())
+    }
+
+    fn random(&self, origin: Vec3, sampler: &mut Samplers) -> Vec3 {
+        // Uniform over the area, folding the unit square onto the triangle
+        let (r1, r2) = sampler.next_2d();
+        let sqrt_r1 = r1.sqrt();
+        let [a, b, c] = self.vertices;
+        let p = (1.0 - sqrt_r1) * a + sqrt_r1 * (1.0 - r2) * b + sqrt_r1 * r2 * c;
+        p - origin
+    }
+}
+
+/// Möller–Trumbore intersection, returning the distance along the ray and
+/// the barycentric coordinates of the hit.
+pub fn intersect(vertices: [Vec3; 3], r: Ray, t_min: f32, t_max: f32) -> Option<(f32, [f32; 3])> {
+    let [a, b, c] = vertices;
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let pvec = Vec3::cross(r.direction, edge2);
+    let det = Vec3::dot(edge1, pvec);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let tvec = r.origin - a;
+    let b1 = Vec3::dot(tvec, pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = Vec3::cross(tvec, edge1);
+    let b2 = Vec3::dot(r.direction, qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    let t = Vec3::dot(edge2, qvec) * inv_det;
+    if t < t_min || t_max < t {
+        return None;
+    }
+    Some((t, [1.0 - b1 - b2, b1, b2]))
+}
+
+/// Replaces the normal of `rec` with an interpolated vertex normal. The side
+/// that was hit is still decided by the geometric normal.
+pub fn set_shading_normal(rec: &mut HitRecord, geometric_normal: Vec3, shading: Vec3) {
+    if shading.near_zero() {
+        return;
+    }
+    let shading = shading.unit();
+    let oriented = if Vec3::dot(shading, geometric_normal) < 0.0 {
+        -shading
+    } else {
+        shading
+    };
+    rec.normal = if rec.front_face { oriented } else { -oriented };
+}
@@ -1,9 +1,5 @@
-use rand::SeedableRng;
-use rand::prelude::*;
-use rand::rngs::SmallRng;
-use std::cell::RefCell;
+use crate::sampler::Sampler;
 use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub, SubAssign};
-use std::time::{SystemTime, UNIX_EPOCH};
 
 #[repr(C, align(16))]
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
@@ -12,15 +8,6 @@ pub struct Vec3 {
     _pad: f32,
 }
 
-thread_local! {
-    static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::seed_from_u64(
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos() as u64
-    ));
-}
-
 impl Vec3 {
     pub const ZERO: Vec3 = Vec3 {
         e: [0.0, 0.0, 0.0],
@@ -82,6 +69,13 @@ impl Vec3 {
         self.e[0] * self.e[0] + self.e[1] * self.e[1] + self.e[2] * self.e[2]
     }
 
+    /// Relative luminance of a linear Rec. 709 color.
+    #[inline]
+    #[must_use]
+    pub fn luminance(self) -> f32 {
+        0.2126 * self.e[0] + 0.7152 * self.e[1] + 0.0722 * self.e[2]
+    }
+
     #[inline]
     #[must_use]
     pub fn dot(u: Vec3, v: Vec3) -> f32 {
@@ -98,6 +92,25 @@ impl Vec3 {
         )
     }
 
+    #[inline]
+    #[must_use]
+    pub fn min(u: Vec3, v: Vec3) -> Vec3 {
+        Vec3::new(u.e[0].min(v.e[0]), u.e[1].min(v.e[1]), u.e[2].min(v.e[2]))
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn max(u: Vec3, v: Vec3) -> Vec3 {
+        Vec3::new(u.e[0].max(v.e[0]), u.e[1].max(v.e[1]), u.e[2].max(v.e[2]))
+    }
+
+    /// Applies `f` to every component.
+    #[inline]
+    #[must_use]
+    pub fn map(self, f: impl Fn(f32) -> f32) -> Vec3 {
+        Vec3::new(f(self.e[0]), f(self.e[1]), f(self.e[2]))
+    }
+
     #[inline]
     #[must_use]
     pub fn unit(self) -> Vec3 {
@@ -110,45 +123,41 @@ impl Vec3 {
         }
     }
 
-    pub fn random() -> Vec3 {
-        RNG.with(|rng| {
-            let mut rng = rng.borrow_mut();
-            Vec3::new(rng.random(), rng.random(), rng.random())
-        })
-    }
-
-    pub fn random_range(min: f32, max: f32) -> Vec3 {
-        RNG.with(|rng| {
-            let mut rng = rng.borrow_mut();
-            Vec3::new(
-                rng.random_range(min..max),
-                rng.random_range(min..max),
-                rng.random_range(min..max),
-            )
-        })
-    }
-
-    pub fn random_in_unit_sphere() -> Vec3 {
-        RNG.with(|rng| {
-            let mut rng = rng.borrow_mut();
-            loop {
-                let x = rng.random_range(-1.0..1.0);
-                let y = rng.random_range(-1.0..1.0);
-                let z = rng.random_range(-1.0..1.0);
-                let len_sq = x * x + y * y + z * z;
-                if len_sq < 1.0 && len_sq > 1e-8 {
-                    return Vec3::new(x, y, z);
-                }
+    pub fn random(sampler: &mut impl Sampler) -> Vec3 {
+        Vec3::new(sampler.next_1d(), sampler.next_1d(), sampler.next_1d())
+    }
+
+    pub fn random_range(sampler: &mut impl Sampler, min: f32, max: f32) -> Vec3 {
+        Vec3::new(
+            sampler.range(min, max),
+            sampler.range(min, max),
+            sampler.range(min, max),
+        )
+    }
+
+    pub fn random_in_unit_sphere(sampler: &mut impl Sampler) -> Vec3 {
+        loop {
+            let x = sampler.range(-1.0, 1.0);
+            let y = sampler.range(-1.0, 1.0);
+            let z = sampler.range(-1.0, 1.0);
+            let len_sq = x * x + y * y + z * z;
+            if len_sq < 1.0 && len_sq > 1e-8 {
+                return Vec3::new(x, y, z);
             }
-        })
+        }
     }
 
-    pub fn random_unit_vector() -> Vec3 {
-        Vec3::random_in_unit_sphere().unit()
+    /// Uniform direction on the sphere, warped from two sample dimensions.
+    pub fn random_unit_vector(sampler: &mut impl Sampler) -> Vec3 {
+        let (u, v) = sampler.next_2d();
+        let z = 1.0 - 2.0 * u;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * std::f32::consts::PI * v;
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
     }
 
-    pub fn random_in_hemisphere(normal: Vec3) -> Vec3 {
-        let in_unit_sphere = Vec3::random_in_unit_sphere();
+    pub fn random_in_hemisphere(sampler: &mut impl Sampler, normal: Vec3) -> Vec3 {
+        let in_unit_sphere = Vec3::random_in_unit_sphere(sampler);
         if Vec3::dot(in_unit_sphere, normal) > 0.0 {
             in_unit_sphere
         } else {
@@ -178,13 +187,11 @@ impl Vec3 {
         r_out_perp + r_out_parallel
     }
 
-    pub fn random_in_unit_disk() -> Vec3 {
-        RNG.with(|rng| {
-            let mut rng = rng.borrow_mut();
-            let theta = rng.random::<f32>() * 2.0 * std::f32::consts::PI;
-            let r = rng.random::<f32>().sqrt();
-            Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
-        })
+    pub fn random_in_unit_disk(sampler: &mut impl Sampler) -> Vec3 {
+        let (u, v) = sampler.next_2d();
+        let theta = u * 2.0 * std::f32::consts::PI;
+        let r = v.sqrt();
+        Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
     }
 }
 
@@ -240,6 +247,18 @@ impl Mul<Vec3> for f32 {
     }
 }
 
+impl Div for Vec3 {
+    type Output = Self;
+    #[inline]
+    fn div(self, other: Self) -> Self::Output {
+        Vec3::new(
+            self.e[0] / other.e[0],
+            self.e[1] / other.e[1],
+            self.e[2] / other.e[2],
+        )
+    }
+}
+
 impl Div<f32> for Vec3 {
     type Output = Self;
     #[inline]
@@ -296,3 +315,9 @@ impl DivAssign<f32> for Vec3 {
         *self *= 1.0 / other;
     }
 }
+
+/// Fails unless `a` and `b` only differ by rounding.
+#[cfg(test)]
+pub fn assert_near(a: Vec3, b: Vec3) {
+    assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+}